@@ -2,9 +2,10 @@ use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, Ordering};
 use swc_core::{
-    common::{SourceMapper, Spanned, SyntaxContext, DUMMY_SP}, // SyntaxContext isn't used in 0.87
+    common::{Mark, SourceMapper, Spanned, SyntaxContext, DUMMY_SP}, // SyntaxContext isn't used in 0.87
     ecma::{
         ast::{Id, *}, // don't import Ident and IdentName use compat functions
+        transforms::base::resolver,
         visit::{Visit, VisitMut, VisitMutWith, VisitWith},
     },
     plugin::{plugin_transform, proxies::TransformPluginProgramMetadata},
@@ -75,6 +76,12 @@ fn make_assign_left_member(obj: Expr, prop: CpIdentName) -> swc_core::ecma::ast:
 
 static GLOBAL_ATTRIBUTES_ADDED: AtomicBool = AtomicBool::new(false);
 
+// Local name bound by the `import { createContext } from "react"` that `ensure_provider_inline`
+// injects — unlike `wrapper_tag`/`provider_ident` (names this plugin only ever synthesizes as a
+// JSX tag/inline `FnDecl`, never as an import), this is the one concrete import the provider
+// machinery actually brings in, so it's the real target for `DeadImportEliminator` to track.
+const PROVIDER_IMPORT_LOCAL: &str = "createContext";
+
 // -----------------------------------------------------------------------------
 // Encoding & filename helpers
 // -----------------------------------------------------------------------------
@@ -112,6 +119,291 @@ fn normalize_filename(filename: &str) -> String {
     s
 }
 
+// -----------------------------------------------------------------------------
+// Resolver: canonicalize import specifiers before they land in graph rows
+// -----------------------------------------------------------------------------
+
+/// Canonicalizes a module specifier relative to its referring file, the way bundler resolvers
+/// do, but without filesystem access (the plugin runs sandboxed): relative specifiers are joined
+/// against the referrer's directory, configured tsconfig-style `paths` aliases are substituted,
+/// and `.tsx` (the first of `RESOLVE_EXTS`) is appended when the result does not already end in
+/// one of `RESOLVE_EXTS`. No `index.*` candidate is produced, and which file actually exists is
+/// not checked. Bare npm-style specifiers are left untouched and flagged as external.
+#[derive(Clone, Default)]
+struct Resolver {
+    /// `(alias_prefix, target_prefix)`, e.g. `("@/", "src/")` for a tsconfig `"@/*": ["src/*"]`.
+    aliases: Vec<(String, String)>,
+}
+
+/// Extensions that count as already resolved; the first is the one appended to a resolved
+/// specifier that ends in none of them.
+const RESOLVE_EXTS: &[&str] = &[".tsx", ".ts", ".jsx", ".js"];
+
+/// Upper bound on `export { x } from '...'` hops `resolve_cross_module` will
+/// follow before giving up, so a diamond of barrel re-exports can't spin forever.
+const MAX_REEXPORT_HOPS: usize = 8;
+
+/// Bundled, sorted catalog of well-known Web/DOM/runtime global roots recognized by
+/// `match_runtime_global` and tagged as `ProvNode::Runtime` instead of a generic
+/// `Member`/`Call`/`Ctor`. Covers both storage/crypto/timing APIs reached via member
+/// access (`localStorage.getItem`, `Date.now`, `performance.now`) and DOM/Web global
+/// constructors reached via `new X(...)`. Extend via the `runtimeGlobals` config key
+/// rather than editing this list, so teams can add their own globals without a patch.
+/// Kept sorted for readability; lookup itself is via `runtime_globals: HashSet<String>`.
+const BUILTIN_RUNTIME_GLOBALS: &[&str] = &[
+    "AbortController",
+    "AnalyserNode",
+    "Animation",
+    "Audio",
+    "AudioContext",
+    "Blob",
+    "BroadcastChannel",
+    "CustomEvent",
+    "DOMParser",
+    "Date",
+    "Element",
+    "Event",
+    "EventTarget",
+    "FileReader",
+    "FormData",
+    "Headers",
+    "IntersectionObserver",
+    "MutationObserver",
+    "Notification",
+    "PerformanceObserver",
+    "Request",
+    "ResizeObserver",
+    "Response",
+    "URL",
+    "URLSearchParams",
+    "WebSocket",
+    "Worker",
+    "crypto",
+    "indexedDB",
+    "localStorage",
+    "performance",
+    "sessionStorage",
+];
+
+impl Resolver {
+    fn new(aliases: Vec<(String, String)>) -> Self {
+        Self { aliases }
+    }
+
+    fn join_relative(referrer_dir: &str, specifier: &str) -> String {
+        let mut parts: Vec<&str> = if referrer_dir.is_empty() {
+            vec![]
+        } else {
+            referrer_dir.split('/').collect()
+        };
+        for seg in specifier.split('/') {
+            match seg {
+                "." | "" => {}
+                ".." => {
+                    parts.pop();
+                }
+                seg => parts.push(seg),
+            }
+        }
+        parts.join("/")
+    }
+
+    fn with_ext_guess(path: String) -> String {
+        if RESOLVE_EXTS.iter().any(|e| path.ends_with(e)) {
+            return path;
+        }
+        // No fs access to probe which of `path.tsx`/`path/index.ts`/etc. actually exists;
+        // record the single highest-priority guess and let a host-side aggregator disambiguate.
+        format!("{}{}", path, RESOLVE_EXTS[0])
+    }
+
+    /// Returns `(resolved, is_external)`. `is_external` is true for bare/unresolvable npm
+    /// package specifiers, which are returned unchanged.
+    fn resolve(&self, specifier: &str, referrer_file: &str) -> (String, bool) {
+        for (alias_prefix, target_prefix) in &self.aliases {
+            if let Some(rest) = specifier.strip_prefix(alias_prefix.as_str()) {
+                return (Self::with_ext_guess(format!("{}{}", target_prefix, rest)), false);
+            }
+        }
+        if specifier.starts_with('.') {
+            let dir = referrer_file
+                .rsplit_once('/')
+                .map(|(dir, _)| dir)
+                .unwrap_or("");
+            return (Self::with_ext_guess(Self::join_relative(dir, specifier)), false);
+        }
+        if specifier.starts_with('/') {
+            return (Self::with_ext_guess(specifier.to_string()), false);
+        }
+        (specifier.to_string(), true)
+    }
+}
+
+/// Where `inject_graph_stmt` hands the per-module `ModuleGraph` off to tooling.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GraphOutput {
+    /// Mutate `globalThis.__CPX_GRAPH` at runtime via `new Function(...)` (existing behavior).
+    Runtime,
+    /// Emit a single leading `/*__CPX_GRAPH__ {json}*/` block comment a post-processor can
+    /// extract statically without executing the bundle.
+    Comment,
+    /// Emit `export const __CPX_GRAPH_<filekey> = JSON.parse("...")` so bundlers/extensions can
+    /// statically read the graph off the module's exports.
+    Export,
+}
+
+/// `config.stampPredicate` sentinel modes — `"pascalCase"` (default) keeps the existing
+/// uppercase-first-letter heuristic, `"all"` stamps every export regardless of casing.
+#[derive(Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum StampMode {
+    PascalCase,
+    All,
+}
+
+/// `config.stampPredicate` — either one of the [`StampMode`] sentinels, or an explicit
+/// allow-list of export names to stamp and nothing else (e.g. for a codebase that names
+/// components `page`/`layout` per Next.js App Router convention instead of PascalCase).
+#[derive(Clone, serde::Deserialize)]
+#[serde(untagged)]
+enum StampPredicate {
+    Mode(StampMode),
+    Names(Vec<String>),
+}
+
+/// Serializable form of an swc `Id` (`(JsWord, SyntaxContext)`): the display name alongside the
+/// raw hygiene context index, so `DefRow`/`ExportRow`/`MutationRow` can distinguish shadowed
+/// bindings that share a symbol (`const user` at module scope vs. `const user` in a nested
+/// block) instead of colliding on `sym.to_string()` alone. Requires `resolver` to have run over
+/// the program first — otherwise every `Ident` keeps the same empty `SyntaxContext`.
+fn id_pair(id: &Id) -> (String, u32) {
+    (id.0.to_string(), id.1.as_u32())
+}
+
+// Index of the first `m.body` item past any leading string-literal directives (e.g. `"use
+// client"`), i.e. where a helper/provider preamble should be inserted. Free function (rather than
+// a `CodePressTransform` method) so `DeadImportEliminator` can also use it to locate that same
+// preamble without needing a whole transform instance.
+fn directive_insert_index(m: &Module) -> usize {
+    let mut idx = 0;
+    for item in &m.body {
+        if let ModuleItem::Stmt(Stmt::Expr(ExprStmt { expr, .. })) = item {
+            if let Expr::Lit(Lit::Str(_)) = &**expr {
+                idx += 1;
+                continue;
+            }
+        }
+        break;
+    }
+    idx
+}
+
+/// Recursively collects every bound `Ident` out of a (possibly destructuring) `Pat`, analogous
+/// to swc_ecma_utils' `DestructuringFinder` — descends `ObjectPat`/`ArrayPat`/`RestPat`/
+/// `AssignPat`/`KeyValuePatProp` so `const { a, b: c } = obj` and `const [x, ...rest] = arr`
+/// surface every bound name, not just a top-level `Pat::Ident`.
+fn collect_pat_idents(pat: &Pat, out: &mut Vec<Ident>) {
+    match pat {
+        Pat::Ident(bi) => out.push(bi.id.clone()),
+        Pat::Array(arr) => {
+            for elem in arr.elems.iter().filter_map(|e| e.as_ref()) {
+                collect_pat_idents(elem, out);
+            }
+        }
+        Pat::Object(obj) => {
+            for prop in &obj.props {
+                match prop {
+                    ObjectPatProp::KeyValue(kv) => collect_pat_idents(&kv.value, out),
+                    ObjectPatProp::Assign(a) => out.push(a.key.id.clone()),
+                    ObjectPatProp::Rest(r) => collect_pat_idents(&r.arg, out),
+                }
+            }
+        }
+        Pat::Rest(r) => collect_pat_idents(&r.arg, out),
+        Pat::Assign(a) => collect_pat_idents(&a.left, out),
+        Pat::Expr(_) | Pat::Invalid(_) => {}
+    }
+}
+
+/// Matches `import("./x")`/`import('./x')` — a dynamic import call with a string-literal source —
+/// mirroring the shape `swc_ecma_transforms_module`'s `ignore_dynamic`/`make_dynamic_import`
+/// helpers look for. Returns `None` for a non-import callee or a non-literal specifier (e.g.
+/// `import(path)`), which this plugin can't resolve statically anyway.
+fn match_dynamic_import_source(call: &CallExpr) -> Option<String> {
+    if !matches!(call.callee, Callee::Import(_)) {
+        return None;
+    }
+    match call.args.get(0) {
+        Some(ExprOrSpread { spread: None, expr }) => match &**expr {
+            Expr::Lit(Lit::Str(s)) => Some(s.value.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Walks down a `Member`/`Ident` chain to the root identifier, e.g. `a.b.c` -> `a`. Mirrors the
+/// root-finding half of `static_member_path`'s `walk`, but returns the `Ident` itself (so a
+/// caller can look it up in `self.bindings` by `Id`) instead of stringifying it.
+fn member_root_ident(expr: &Expr) -> Option<&Ident> {
+    match expr {
+        Expr::Ident(i) => Some(i),
+        Expr::Member(m) => member_root_ident(&m.obj),
+        _ => None,
+    }
+}
+
+/// Replace characters that aren't valid in a JS identifier with `_` so an encoded file key can
+/// be spliced into `__CPX_GRAPH_<filekey>`.
+fn sanitize_ident_suffix(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Typed knobs for the tunables this transform used to hardcode, nested under the
+/// top-level `config` key so it can grow without disturbing the ad hoc flags (`stampCallsites`,
+/// `pathAliases`, …) the rest of `CodePressTransform::new` still reads straight off the raw
+/// JSON blob. `deny_unknown_fields` means a typo here fails loudly instead of being silently
+/// ignored, the way module transforms validate their own `Config`.
+#[derive(serde::Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase", deny_unknown_fields, default)]
+struct Config {
+    wrapper_tag: Option<String>,
+    provider_ident: Option<String>,
+    /// Inject the inline `__CPProvider`/context plumbing into `.tsx` modules (the "wrap
+    /// providers" phase). Default on.
+    enable_provider: Option<bool>,
+    /// Inject the `__CP_stamp` helper and emit any stamping calls at all (the "stamp exports"
+    /// phase). Default on; the separate `stampCallsites` flag only controls the narrower
+    /// per-JSX-callsite form.
+    enable_stamping: Option<bool>,
+    /// Caps `trace_expr` recursion; exceeding it emits `ProvNode::Truncated` instead of
+    /// silently stopping. Default 8 (the previous hardcoded limit).
+    max_trace_depth: Option<usize>,
+    /// When set, `rank_candidates`/`aggregate_kinds` drop any `ProvNode` whose kind tag
+    /// (`"literal"`, `"import"`, `"fetch"`, …) isn't in this list.
+    prov_node_kinds: Option<Vec<String>>,
+    /// Mutate `n.src.value` on imports/re-exports to `Resolver::resolve`'s output in place
+    /// (internal specifiers only — bare/external ones are left as written). Default off: most
+    /// consumers only want the resolved path recorded on `ImportRow`/`ReexportRow`, not the
+    /// compiled output's module specifiers changed out from under them.
+    rewrite_import_specifiers: Option<bool>,
+    /// Skip `import type { ... }` / `export type { ... } from '...'` / a type-only `export *`
+    /// entirely — no graph row, no specifier rewrite — since they compile away and have no
+    /// runtime target to resolve. Mirrors Aleph's `Resolver` `type_only` option. Default on.
+    skip_type_only_imports: Option<bool>,
+    /// Call `inject_graph_stmt` at all. Default on; turn off to run `enableStamping` (or any
+    /// other phase) standalone without the `__CPX_GRAPH` output landing in the compiled module.
+    emit_module_graph: Option<bool>,
+    /// Populate `ModuleGraph::literal_index` from exported `const`/`let`/`var` initializers. Default
+    /// on; turn off for a pure-analysis pass that skips the recursive literal walk.
+    harvest_literal_index: Option<bool>,
+    /// Overrides the default PascalCase check for which exports get stamped. See
+    /// [`StampPredicate`].
+    stamp_predicate: Option<StampPredicate>,
+}
+
 // -----------------------------------------------------------------------------
 // Transform state
 // -----------------------------------------------------------------------------
@@ -120,21 +412,90 @@ pub struct CodePressTransform {
     repo_name: Option<String>,
     branch_name: Option<String>,
     source_map: Option<std::sync::Arc<dyn SourceMapper>>,
+    comments: Option<std::sync::Arc<dyn swc_core::common::comments::Comments>>,
 
     // Provenance helpers
     bindings: HashMap<Id, Binding>,
 
+    // Per-transform interners so `create_encoded_path_attr`, `span_file_lines` and
+    // `file_from_span` normalize/encode a given source path at most once, even though dozens of
+    // JSX elements in a file share the same `module_file`. Other call sites (the module-level
+    // stamp fingerprint, callsite stamps, graph file keys, the per-element callsite target and
+    // span-map file) call `normalize_filename`/`xor_encode` directly and are not memoized.
+    path_intern: std::cell::RefCell<HashMap<String, String>>, // raw -> normalized
+    encoded_intern: std::cell::RefCell<HashMap<String, String>>, // normalized -> xor-encoded
+
     // Always-on behavior:
     wrapper_tag: String,    // DOM wrapper tag (display: contents)
     provider_ident: String, // __CPProvider (inline injected)
+    // `Atom` copies of the two fields above, interned once here instead of re-comparing
+    // `id.sym.as_ref() == self.wrapper_tag` byte-by-byte on every JSX element `is_synthetic_element`
+    // checks — same representation `Ident::sym` already uses, so the comparison is a cheap
+    // interned-atom check rather than a string slice compare.
+    wrapper_tag_atom: swc_core::ecma::atoms::Atom,
+    provider_ident_atom: swc_core::ecma::atoms::Atom,
     inserted_provider_import: bool,
     inserted_stamp_helper: bool,
+    // Number of module items already inserted by `ensure_provider_inline`/`ensure_stamp_helper_inline`,
+    // so the callsite-stamp flush in `visit_mut_module` can insert after them without recomputing
+    // their exact shapes.
+    helper_item_count: usize,
     stamp_callsites: bool,
     callsite_symbols: HashSet<String>,
+    // Spans (lo, hi) of dynamic `import()` calls already pushed to `graph.dyn_imports`, so the
+    // `await import(...)` fast path (checked in `visit_mut_await_expr`) and the generic
+    // `Callee::Import` fast path (checked in `visit_mut_call_expr`) don't double-record the same
+    // call when the visitor recurses into the awaited expression.
+    dyn_import_seen: HashSet<(u32, u32)>,
+    // `__CP_stamp(Foo, "<fp>#Foo", "<fp>")` statements for local component callsites, accumulated
+    // while walking JSX elements and flushed as module-top statements once per module in
+    // `visit_mut_module` — mutating `m.body` mid-JSX-visit would require threading the module
+    // back through every nested visitor, so we collect and flush instead.
+    pending_callsite_stmts: Vec<ModuleItem>,
+    // `config.enableProvider`/`config.enableStamping` — master switches, off by exception
+    // rather than the norm, so most builds never touch them.
+    enable_provider: bool,
+    enable_stamping: bool,
+    // `config.maxTraceDepth` — enforced by `trace_expr` instead of the old hardcoded `8`.
+    max_trace_depth: usize,
+    // `config.provNodeKinds` — when set, scopes `rank_candidates`/`aggregate_kinds` to these
+    // ProvNode kind tags only.
+    prov_node_kinds: Option<HashSet<String>>,
+    // `config.rewriteImportSpecifiers` — actually edit import/re-export source text, not just
+    // record the resolved path on the graph row.
+    rewrite_import_specifiers: bool,
+    // `config.skipTypeOnlyImports` — drop `import type`/`export type` from the graph and leave
+    // their specifiers untouched.
+    skip_type_only_imports: bool,
+    // `config.emitModuleGraph` — gates the `inject_graph_stmt` call; the graph is still built
+    // internally (cheap, and other phases don't depend on it having run) but never lands in
+    // the compiled module when this is off.
+    emit_module_graph: bool,
+    // `config.harvestLiteralIndex` — gates the recursive literal walk over export initializers.
+    harvest_literal_index_enabled: bool,
+    // `config.stampPredicate` — defaults to `StampMode::PascalCase`, matching the old hardcoded
+    // uppercase-first-letter check.
+    stamp_predicate: StampPredicate,
+    // Emit `:startCol-endCol` alongside line numbers (`emitColumns` config flag). Defaults to on,
+    // mirroring SWC codegen's `emit_source_map_columns` switch.
+    emit_columns: bool,
+    // How `inject_graph_stmt` hands the collected `ModuleGraph` off to tooling.
+    graph_output: GraphOutput,
+    // Canonicalizes import/re-export specifiers before they land in graph rows.
+    resolver: Resolver,
+    // Host-supplied export tables for already-processed sibling modules, keyed by
+    // resolved path, so `trace_expr` can follow an import past this module's boundary.
+    imported_graphs: HashMap<String, RemoteModuleFacts>,
+    // `BUILTIN_RUNTIME_GLOBALS` plus whatever the host appends via `runtimeGlobals`.
+    runtime_globals: HashSet<String>,
 
     // -------- module graph (this module only) --------
     module_file: Option<String>,
     graph: ModuleGraph,
+    // Lexical scope stack: top is the innermost enclosing module/function/arrow/block. Pushed
+    // in `enter_scope` and popped in `exit_scope`, always in save/restore pairs so an early
+    // return from a visitor never leaves a stale scope on the stack.
+    scope_stack: Vec<usize>,
 
     // Skips: components we should not wrap (to avoid interfering with pass-through libs)
     skip_components: std::collections::HashSet<String>,      // e.g., ["Slot", "Link"]
@@ -142,24 +503,10 @@ pub struct CodePressTransform {
 }
 
 impl CodePressTransform {
-    /// Finds the index immediately after the directive prologue (e.g. "use client", "use strict").
-    /// Any injected statements should be inserted at this index to avoid preceding directives.
-    fn directive_insert_index(&self, m: &Module) -> usize {
-        let mut idx = 0;
-        for item in &m.body {
-            if let ModuleItem::Stmt(Stmt::Expr(ExprStmt { expr, .. })) = item {
-                if let Expr::Lit(Lit::Str(_)) = &**expr {
-                    idx += 1;
-                    continue;
-                }
-            }
-            break;
-        }
-        idx
-    }
     pub fn new(
         mut config: HashMap<String, serde_json::Value>,
         source_map: Option<std::sync::Arc<dyn SourceMapper>>,
+        comments: Option<std::sync::Arc<dyn swc_core::common::comments::Comments>>,
     ) -> Self {
         let repo_name = config
             .remove("repo_name")
@@ -168,12 +515,116 @@ impl CodePressTransform {
             .remove("branch_name")
             .and_then(|v| v.as_str().map(|s| s.to_string()));
 
-        let wrapper_tag = "codepress-marker".to_string();
-        let provider_ident = "__CPProvider".to_string();
-        let stamp_callsites = config
-            .remove("stampCallsites")
+        let typed_config: Config = config
+            .remove("config")
+            .map(|v| serde_json::from_value(v).expect("invalid `config` for codepress-swc-plugin"))
+            .unwrap_or_default();
+
+        let wrapper_tag = typed_config
+            .wrapper_tag
+            .clone()
+            .unwrap_or_else(|| "codepress-marker".to_string());
+        let provider_ident = typed_config
+            .provider_ident
+            .clone()
+            .unwrap_or_else(|| "__CPProvider".to_string());
+        let enable_provider = typed_config.enable_provider.unwrap_or(true);
+        let enable_stamping = typed_config.enable_stamping.unwrap_or(true);
+        let max_trace_depth = typed_config.max_trace_depth.unwrap_or(8);
+        let prov_node_kinds: Option<HashSet<String>> = typed_config
+            .prov_node_kinds
+            .map(|kinds| kinds.into_iter().collect());
+        let rewrite_import_specifiers = typed_config.rewrite_import_specifiers.unwrap_or(false);
+        let skip_type_only_imports = typed_config.skip_type_only_imports.unwrap_or(true);
+        let emit_module_graph = typed_config.emit_module_graph.unwrap_or(true);
+        let harvest_literal_index_enabled = typed_config.harvest_literal_index.unwrap_or(true);
+        let stamp_predicate = typed_config
+            .stamp_predicate
+            .unwrap_or(StampPredicate::Mode(StampMode::PascalCase));
+
+        let stamp_callsites = enable_stamping
+            && config
+                .remove("stampCallsites")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true);
+        let emit_columns = config
+            .remove("emitColumns")
             .and_then(|v| v.as_bool())
             .unwrap_or(true);
+        let graph_output = match config.remove("graphOutput").and_then(|v| v.as_str().map(|s| s.to_string())) {
+            Some(ref s) if s == "comment" => GraphOutput::Comment,
+            Some(ref s) if s == "export" => GraphOutput::Export,
+            _ => GraphOutput::Runtime,
+        };
+        let path_aliases: Vec<(String, String)> = config
+            .remove("pathAliases")
+            .and_then(|v| v.as_object().cloned())
+            .map(|obj| {
+                obj.into_iter()
+                    .filter_map(|(alias, target)| {
+                        let target = target.as_str()?.to_string();
+                        Some((
+                            alias.trim_end_matches('*').to_string(),
+                            target.trim_end_matches('*').to_string(),
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let resolver = Resolver::new(path_aliases);
+
+        let imported_graphs: HashMap<String, RemoteModuleFacts> = config
+            .remove("importedModuleGraphs")
+            .and_then(|v| v.as_object().cloned())
+            .map(|modules| {
+                modules
+                    .into_iter()
+                    .map(|(resolved_path, facts)| {
+                        let mut exports = HashMap::new();
+                        if let Some(arr) = facts.get("exports").and_then(|v| v.as_array()) {
+                            for row in arr {
+                                if let (Some(exported), Some(local)) = (
+                                    row.get("exported").and_then(|v| v.as_str()),
+                                    row.get("local").and_then(|v| v.as_str()),
+                                ) {
+                                    let kind = row
+                                        .get("kind")
+                                        .and_then(|v| v.as_str())
+                                        .map(|s| s.to_string());
+                                    exports.insert(exported.to_string(), (local.to_string(), kind));
+                                }
+                            }
+                        }
+                        let mut reexports = HashMap::new();
+                        if let Some(arr) = facts.get("reexports").and_then(|v| v.as_array()) {
+                            for row in arr {
+                                if let (Some(exported), Some(resolved), Some(imported)) = (
+                                    row.get("exported").and_then(|v| v.as_str()),
+                                    row.get("resolved").and_then(|v| v.as_str()),
+                                    row.get("imported").and_then(|v| v.as_str()),
+                                ) {
+                                    reexports.insert(
+                                        exported.to_string(),
+                                        (resolved.to_string(), imported.to_string()),
+                                    );
+                                }
+                            }
+                        }
+                        (resolved_path, RemoteModuleFacts { exports, reexports })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut runtime_globals: HashSet<String> =
+            BUILTIN_RUNTIME_GLOBALS.iter().map(|s| s.to_string()).collect();
+        if let Some(extra) = config.remove("runtimeGlobals").and_then(|v| v.as_array().cloned()) {
+            for v in extra {
+                if let Some(s) = v.as_str() {
+                    runtime_globals.insert(s.to_string());
+                }
+            }
+        }
 
         // Parse optional skip lists from config
         fn read_string_set(map: &mut HashMap<String, serde_json::Value>, key: &str) -> std::collections::HashSet<String> {
@@ -212,13 +663,35 @@ impl CodePressTransform {
             repo_name,
             branch_name,
             source_map,
+            comments,
             bindings: Default::default(),
+            path_intern: Default::default(),
+            encoded_intern: Default::default(),
+            wrapper_tag_atom: wrapper_tag.clone().into(),
+            provider_ident_atom: provider_ident.clone().into(),
             wrapper_tag,
             provider_ident,
             inserted_provider_import: false,
             inserted_stamp_helper: false,
+            helper_item_count: 0,
+            pending_callsite_stmts: Vec::new(),
+            dyn_import_seen: HashSet::new(),
             stamp_callsites,
             callsite_symbols: HashSet::new(),
+            enable_provider,
+            enable_stamping,
+            max_trace_depth,
+            prov_node_kinds,
+            rewrite_import_specifiers,
+            skip_type_only_imports,
+            emit_module_graph,
+            harvest_literal_index_enabled,
+            stamp_predicate,
+            emit_columns,
+            graph_output,
+            resolver,
+            imported_graphs,
+            runtime_globals,
             module_file: None,
             graph: ModuleGraph {
                 imports: vec![],
@@ -227,7 +700,13 @@ impl CodePressTransform {
                 defs: vec![],
                 mutations: vec![],
                 literal_index: vec![],
+                scopes: vec![],
+                bailouts: vec![],
+                dyn_imports: vec![],
+                resolved_exports: vec![],
+                span_map: vec![],
             },
+            scope_stack: vec![],
             skip_components,
             skip_member_roots,
         }
@@ -235,6 +714,32 @@ impl CodePressTransform {
 
     // ---------- helpers ----------
 
+    /// Normalize a raw path, memoizing on `path_intern` so a given raw path string
+    /// (usually the single `module_file` shared by every element in this file) is only
+    /// ever run through `normalize_filename` once per transform.
+    fn normalize_interned(&self, raw: &str) -> String {
+        if let Some(hit) = self.path_intern.borrow().get(raw) {
+            return hit.clone();
+        }
+        let normalized = normalize_filename(raw);
+        self.path_intern
+            .borrow_mut()
+            .insert(raw.to_string(), normalized.clone());
+        normalized
+    }
+
+    /// XOR-encode an already-normalized path, memoizing on `encoded_intern`.
+    fn encode_interned(&self, normalized: &str) -> String {
+        if let Some(hit) = self.encoded_intern.borrow().get(normalized) {
+            return hit.clone();
+        }
+        let encoded = xor_encode(normalized);
+        self.encoded_intern
+            .borrow_mut()
+            .insert(normalized.to_string(), encoded.clone());
+        encoded
+    }
+
     fn span_file_lines(&self, s: swc_core::common::Span) -> String {
         if s.is_dummy() {
             return "unknown:0-0".to_string();
@@ -242,12 +747,14 @@ impl CodePressTransform {
         if let Some(ref cm) = self.source_map {
             let lo = cm.lookup_char_pos(s.lo());
             let hi = cm.lookup_char_pos(s.hi());
-            return format!(
-                "{}:{}-{}",
-                normalize_filename(&lo.file.name.to_string()),
-                lo.line,
-                hi.line
-            );
+            let file = self.normalize_interned(&lo.file.name.to_string());
+            if self.emit_columns {
+                return format!(
+                    "{}:{}:{}-{}:{}",
+                    file, lo.line, lo.col_display, hi.line, hi.col_display
+                );
+            }
+            return format!("{}:{}-{}", file, lo.line, hi.line);
         }
         "unknown:0-0".to_string()
     }
@@ -258,7 +765,7 @@ impl CodePressTransform {
         }
         if let Some(ref cm) = self.source_map {
             let lo = cm.lookup_char_pos(s.lo());
-            let f = normalize_filename(&lo.file.name.to_string());
+            let f = self.normalize_interned(&lo.file.name.to_string());
             self.module_file.get_or_insert(f.clone());
             return Some(f);
         }
@@ -309,8 +816,9 @@ impl CodePressTransform {
         match name {
             // <codepress-marker> / <__CPProvider> / <__CPX>
             JSXElementName::Ident(id) => {
-                let n = id.sym.as_ref();
-                n == self.wrapper_tag || n == self.provider_ident || n == "__CPX"
+                id.sym == self.wrapper_tag_atom
+                    || id.sym == self.provider_ident_atom
+                    || id.sym.as_ref() == "__CPX"
             }
             // <__CPX.Provider> or anything under __CPProvider/__CPX
             JSXElementName::JSXMemberExpr(m) => {
@@ -320,8 +828,7 @@ impl CodePressTransform {
                     obj = &inner.obj;
                 }
                 if let JSXObject::Ident(root) = obj {
-                    let n = root.sym.as_ref();
-                    n == "__CPX" || n == self.provider_ident
+                    root.sym.as_ref() == "__CPX" || root.sym == self.provider_ident_atom
                 } else {
                     false
                 }
@@ -361,16 +868,21 @@ impl CodePressTransform {
         })
     }
 
-    fn attach_attr_string(attrs: &mut Vec<JSXAttrOrSpread>, key: &str, val: String) {
+    fn attach_attr_string(
+        attrs: &mut Vec<JSXAttrOrSpread>,
+        key: &str,
+        val: String,
+        span: swc_core::common::Span,
+    ) {
         // Do not override existing props; only add if absent
         if Self::has_attr_key(attrs, key) {
             return;
         }
         attrs.push(JSXAttrOrSpread::JSXAttr(JSXAttr {
-            span: DUMMY_SP,
+            span,
             name: JSXAttrName::Ident(cp_ident_name(key.into())),
             value: Some(JSXAttrValue::Lit(Lit::Str(Str {
-                span: DUMMY_SP,
+                span,
                 value: val.into(),
                 raw: None,
             }))),
@@ -430,21 +942,185 @@ impl CodePressTransform {
     fn push_mutation_row(
         &mut self,
         root: String,
+        root_id: Option<Id>,
         path: String,
         kind: &'static str,
         span: swc_core::common::Span,
     ) {
+        // `ensure_stamp_helper_inline` injects `globalThis.__CP_stamp = globalThis.__CP_stamp ||
+        // __CP_stamp` before the main traversal runs, so this visitor sees it like any other
+        // module-level assignment — don't let our own synthetic helper wiring leak into the
+        // emitted graph as a mutation row.
+        if root == "globalThis" && path == ".__CP_stamp" {
+            return;
+        }
         let _ = self.file_from_span(span);
+        let scope = self.cur_scope();
         self.graph.mutations.push(MutationRow {
             root,
+            root_id: root_id.as_ref().map(id_pair),
             path,
             kind,
             span: self.span_file_lines(span),
+            scope,
         });
     }
 
-    // Inject `globalThis.__CPX_GRAPH[file] = JSON.parse("<json>")` via new Function to avoid big AST building.
+    // Pushes a new `ScopeRow` parented at the current top-of-stack scope and returns its id.
+    // Callers must pair this with `exit_scope` — see `visit_mut_fn_decl`/`visit_mut_arrow_expr`/
+    // `visit_mut_block_stmt` for the save/restore pattern.
+    fn enter_scope(&mut self, kind: &'static str, span: swc_core::common::Span) -> usize {
+        let id = self.graph.scopes.len();
+        let parent = self.scope_stack.last().copied();
+        let span = self.span_file_lines(span);
+        self.graph.scopes.push(ScopeRow { id, parent, kind, span });
+        self.scope_stack.push(id);
+        id
+    }
+
+    fn exit_scope(&mut self) {
+        self.scope_stack.pop();
+    }
+
+    fn cur_scope(&self) -> usize {
+        self.scope_stack.last().copied().unwrap_or(0)
+    }
+
+    /// Whether `export_name` should be stamped, per `self.stamp_predicate`. Always false when
+    /// `config.enableStamping` is off, since no `__CP_stamp` helper is injected then.
+    fn is_stampable(&self, export_name: &str) -> bool {
+        if !self.enable_stamping {
+            return false;
+        }
+        match &self.stamp_predicate {
+            StampPredicate::Mode(StampMode::All) => true,
+            StampPredicate::Mode(StampMode::PascalCase) => export_name
+                .chars()
+                .next()
+                .map(|c| c.is_uppercase())
+                .unwrap_or(false),
+            StampPredicate::Names(names) => names.iter().any(|n| n == export_name),
+        }
+    }
+
+    /// Follows a named re-export edge (`export { x } from '...'` / `export { y as x } from
+    /// '...'`) to its origin, using the host-supplied `imported_graphs` facts for whatever
+    /// sibling files it already processed — this plugin only ever sees one module's AST per
+    /// invocation, so it can't walk another file's AST directly. Stops at the first file
+    /// `imported_graphs` doesn't have facts for, and at cycles, tracked via `visited` since
+    /// `export * from`-style chains can loop between modules. Such a stop returns `None` only
+    /// when it happens at `file` itself; past the first hop, the last edge followed is returned
+    /// as `(file, name, None)`.
+    fn resolve_export_target(
+        &self,
+        file: &str,
+        name: &str,
+        visited: &mut HashSet<(String, String)>,
+    ) -> Option<(String, String, Option<String>)> {
+        if !visited.insert((file.to_string(), name.to_string())) {
+            return None;
+        }
+        let facts = self.imported_graphs.get(file)?;
+        if let Some((local, kind)) = facts.exports.get(name) {
+            return Some((file.to_string(), local.clone(), kind.clone()));
+        }
+        if let Some((next_file, next_imported)) = facts.reexports.get(name) {
+            return self
+                .resolve_export_target(next_file, next_imported, visited)
+                .or_else(|| Some((next_file.clone(), next_imported.clone(), None)));
+        }
+        None
+    }
+
+    /// Unions `file`'s non-default exports for an `export * from` edge, following a further
+    /// `export * from` in `file` itself (recorded under the `"*"` key in its `reexports` table)
+    /// transitively. `visited` (by file only, since a wildcard re-export pulls in every name)
+    /// breaks cycles the same way `resolve_export_target` does for named edges.
+    fn collect_wildcard_exports(
+        &self,
+        file: &str,
+        visited: &mut HashSet<String>,
+    ) -> Vec<(String, String, String, Option<String>)> {
+        if !visited.insert(file.to_string()) {
+            return Vec::new();
+        }
+        let facts = match self.imported_graphs.get(file) {
+            Some(f) => f,
+            None => return Vec::new(),
+        };
+        let mut out: Vec<(String, String, String, Option<String>)> = facts
+            .exports
+            .iter()
+            .filter(|(exported, _)| exported.as_str() != "default")
+            .map(|(exported, (local, kind))| {
+                (exported.clone(), file.to_string(), local.clone(), kind.clone())
+            })
+            .collect();
+        if let Some((next_file, _)) = facts.reexports.get("*") {
+            out.extend(self.collect_wildcard_exports(next_file, visited));
+        }
+        out
+    }
+
+    /// Builds `ModuleGraph::resolved_exports`: the transitive closure of this module's own
+    /// `exports` plus every `reexports` edge, resolved all the way to the file that actually
+    /// defines each name. Direct exports look their def kind up in `self.graph.defs` by `Id`;
+    /// re-exports follow `resolve_export_target`/`collect_wildcard_exports` through whatever
+    /// sibling-file facts the host supplied via `importedModuleGraphs`.
+    fn resolve_exports(&mut self) {
+        let current_file = self.current_file();
+        let mut rows: Vec<ResolvedExportRow> = Vec::new();
+
+        for e in &self.graph.exports {
+            let kind = e
+                .local_id
+                .as_ref()
+                .and_then(|id| self.graph.defs.iter().find(|d| &d.id == id))
+                .map(|d| d.kind.to_string());
+            rows.push(ResolvedExportRow {
+                exported: e.exported.clone(),
+                file: current_file.clone(),
+                local_symbol: e.local.clone(),
+                kind,
+            });
+        }
+
+        for r in &self.graph.reexports {
+            if r.exported == "*" {
+                let mut visited = HashSet::new();
+                for (exported, file, local_symbol, kind) in
+                    self.collect_wildcard_exports(&r.resolved, &mut visited)
+                {
+                    rows.push(ResolvedExportRow { exported, file, local_symbol, kind });
+                }
+                continue;
+            }
+            let mut visited = HashSet::new();
+            let resolved = self
+                .resolve_export_target(&r.resolved, &r.imported, &mut visited)
+                .unwrap_or_else(|| (r.resolved.clone(), r.imported.clone(), None));
+            rows.push(ResolvedExportRow {
+                exported: r.exported.clone(),
+                file: resolved.0,
+                local_symbol: resolved.1,
+                kind: resolved.2,
+            });
+        }
+
+        self.graph.resolved_exports = rows;
+    }
+
+    // Hand the collected `ModuleGraph` off to tooling per `self.graph_output`.
     fn inject_graph_stmt(&self, m: &mut Module) {
+        match self.graph_output {
+            GraphOutput::Runtime => self.inject_graph_stmt_runtime(m),
+            GraphOutput::Comment => self.inject_graph_stmt_comment(m),
+            GraphOutput::Export => self.inject_graph_stmt_export(m),
+        }
+    }
+
+    // Inject `globalThis.__CPX_GRAPH[file] = JSON.parse("<json>")` via new Function to avoid big AST building.
+    fn inject_graph_stmt_runtime(&self, m: &mut Module) {
         let file_key = xor_encode(&self.current_file());
         let file_key_json = serde_json::to_string(&file_key).unwrap_or("\"unknown\"".into());
         // graph as JSON string literal passed into JSON.parse
@@ -480,10 +1156,86 @@ impl CodePressTransform {
                 ctxt: SyntaxContext::empty(),
             })),
         }));
-        let insert_at = self.directive_insert_index(m);
+        let insert_at = directive_insert_index(m);
         m.body.insert(insert_at, stmt);
     }
 
+    // Prepend `/*__CPX_GRAPH__ {json}*/` as a leading block comment on the module's first item
+    // (ahead of any directives), so a post-processor can lift it without executing the module.
+    fn inject_graph_stmt_comment(&self, m: &mut Module) {
+        let graph_json = serde_json::to_string(&self.graph).unwrap_or("{}".into());
+        let text = format!("__CPX_GRAPH__ {}", graph_json);
+        if let Some(comments) = &self.comments {
+            let pos = m
+                .body
+                .first()
+                .map(|item| item.span().lo())
+                .unwrap_or_else(|| m.span.lo());
+            comments.add_leading(
+                pos,
+                swc_core::common::comments::Comment {
+                    kind: swc_core::common::comments::CommentKind::Block,
+                    span: DUMMY_SP,
+                    text: text.into(),
+                },
+            );
+        }
+    }
+
+    // `export const __CPX_GRAPH_<filekey> = JSON.parse("<json>")` so the graph rides along as a
+    // statically-extractable export instead of a runtime mutation.
+    fn inject_graph_stmt_export(&self, m: &mut Module) {
+        let file_key = xor_encode(&self.current_file());
+        let ident_name = format!("__CPX_GRAPH_{}", sanitize_ident_suffix(&file_key));
+        let graph_json = serde_json::to_string(&self.graph).unwrap_or("{}".into());
+
+        let parse_call = Expr::Call(CallExpr {
+            span: DUMMY_SP,
+            callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
+                span: DUMMY_SP,
+                obj: Box::new(Expr::Ident(cp_ident("JSON".into()))),
+                prop: MemberProp::Ident(cp_ident_name("parse".into())),
+            }))),
+            args: vec![ExprOrSpread {
+                spread: None,
+                expr: Box::new(Expr::Lit(Lit::Str(Str {
+                    span: DUMMY_SP,
+                    value: graph_json.into(),
+                    raw: None,
+                }))),
+            }],
+            type_args: None,
+            #[cfg(not(feature = "compat_0_87"))]
+            ctxt: SyntaxContext::empty(),
+        });
+
+        let export_decl = ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl {
+            span: DUMMY_SP,
+            decl: Decl::Var(Box::new(VarDecl {
+                span: DUMMY_SP,
+                kind: VarDeclKind::Const,
+                declare: false,
+                decls: vec![VarDeclarator {
+                    span: DUMMY_SP,
+                    name: Pat::Ident(BindingIdent {
+                        id: cp_ident(&ident_name),
+                        type_ann: None,
+                    }),
+                    init: Some(Box::new(parse_call)),
+                    definite: false,
+                }],
+                #[cfg(not(feature = "compat_0_87"))]
+                ctxt: SyntaxContext::empty(),
+            })),
+        }));
+        m.body.push(export_decl);
+    }
+
+    /// Renders `startLine-endLine`, or `startLine:startCol-endLine:endCol` when `emit_columns`
+    /// is on. The column-aware form is still a superset of the old shape: a consumer splitting
+    /// on the *first* ':' after the encoded path still isolates `startLine` on its own, and one
+    /// splitting on the last '-' still isolates the end side, so naive old parsers degrade
+    /// gracefully instead of erroring.
     fn get_line_info(
         &self,
         opening_span: swc_core::common::Span,
@@ -499,20 +1251,53 @@ impl CodePressTransform {
                 return None;
             }
             let end_loc = cm.lookup_char_pos(end_span.hi());
-            Some(format!("{}-{}", start_loc.line, end_loc.line))
+            if self.emit_columns {
+                Some(format!(
+                    "{}:{}-{}:{}",
+                    start_loc.line, start_loc.col_display, end_loc.line, end_loc.col_display
+                ))
+            } else {
+                Some(format!("{}-{}", start_loc.line, end_loc.line))
+            }
         } else {
             None
         }
     }
 
+    // Same lookup as `get_line_info`, but returns the raw (start_line, start_col, end_line,
+    // end_col) tuple instead of a formatted string, for payloads that need structured fields
+    // rather than a `file:line:col-line:col` display string.
+    fn line_col_range(
+        &self,
+        opening_span: swc_core::common::Span,
+        parent_span: Option<swc_core::common::Span>,
+    ) -> Option<(usize, usize, usize, usize)> {
+        if opening_span.is_dummy() {
+            return None;
+        }
+        let cm = self.source_map.as_ref()?;
+        let start_loc = cm.lookup_char_pos(opening_span.lo());
+        let end_span = parent_span.unwrap_or(opening_span);
+        if end_span.is_dummy() {
+            return None;
+        }
+        let end_loc = cm.lookup_char_pos(end_span.hi());
+        Some((
+            start_loc.line,
+            start_loc.col_display,
+            end_loc.line,
+            end_loc.col_display,
+        ))
+    }
+
     fn create_encoded_path_attr(
         &self,
         filename: &str,
         opening_span: swc_core::common::Span,
         parent_span: Option<swc_core::common::Span>,
     ) -> JSXAttrOrSpread {
-        let normalized = normalize_filename(filename);
-        let encoded_path = xor_encode(&normalized);
+        let normalized = self.normalize_interned(filename);
+        let encoded_path = self.encode_interned(&normalized);
 
         let attr_value = if let Some(line_info) = self.get_line_info(opening_span, parent_span) {
             format!("{}:{}", encoded_path, line_info)
@@ -521,10 +1306,10 @@ impl CodePressTransform {
         };
 
         JSXAttrOrSpread::JSXAttr(JSXAttr {
-            span: DUMMY_SP,
+            span: opening_span,
             name: JSXAttrName::Ident(cp_ident_name("codepress-data-fp".into())),
             value: Some(JSXAttrValue::Lit(Lit::Str(Str {
-                span: DUMMY_SP,
+                span: opening_span,
                 value: attr_value.into(),
                 raw: None,
             }))),
@@ -584,8 +1369,14 @@ impl CodePressTransform {
     // ---------- binding collection & tracing ----------
 
     fn collect_bindings(&mut self, program: &Program) {
+        if let Program::Module(m) = program {
+            let _ = self.file_from_span(m.span);
+        }
+        let referrer_file = self.current_file();
         let mut bc = BindingCollector {
             out: &mut self.bindings,
+            resolver: &self.resolver,
+            referrer_file: &referrer_file,
         };
         program.visit_with(&mut bc);
     }
@@ -597,7 +1388,14 @@ impl CodePressTransform {
         depth: usize,
         seen: &mut HashSet<Id>,
     ) {
-        if depth > 8 || chain.len() > 128 {
+        if depth > self.max_trace_depth {
+            chain.push(ProvNode::Truncated {
+                span: self.span_file_lines(expr.span()),
+                reason: "max_trace_depth",
+            });
+            return;
+        }
+        if chain.len() > 128 {
             return;
         }
         match expr {
@@ -632,9 +1430,17 @@ impl CodePressTransform {
                     if let Some(im) = &b.import {
                         chain.push(ProvNode::Import {
                             source: im.source.clone(),
+                            resolved: im.resolved.clone(),
                             imported: im.imported.clone(),
                             span: self.span_file_lines(b.def_span),
                         });
+                        // Namespace imports (`import * as ns`) don't name a single export to
+                        // continue into — only named/default bindings can hop further.
+                        if im.imported != "*" {
+                            if let Some(remote) = self.resolve_cross_module(&im.resolved, &im.imported) {
+                                chain.push(remote);
+                            }
+                        }
                     }
                 }
             }
@@ -646,6 +1452,15 @@ impl CodePressTransform {
                     });
                     return;
                 }
+                if let Some((root, path)) = self.static_member_path(expr) {
+                    if self.match_runtime_global(&root) {
+                        chain.push(ProvNode::Runtime {
+                            api: format!("{}{}", root, path),
+                            span: self.span_file_lines(m.span),
+                        });
+                        return;
+                    }
+                }
                 chain.push(ProvNode::Member {
                     span: self.span_file_lines(m.span),
                 });
@@ -655,13 +1470,55 @@ impl CodePressTransform {
                 }
             }
             Expr::Call(c) => {
-                // TODO: consider better detection for fetching (+ integration to extension/backend)
-                // if let Some(fetch_like) = detect_fetch_like(c) {
-                //     chain.push(ProvNode::Fetch {
-                //         url: fetch_like.url,
-                //         span: self.span_file_lines(c.span),
-                //     });
-                // }
+                if let Some(fetch_like) = self.detect_fetch_like(c) {
+                    chain.push(ProvNode::Fetch {
+                        url: fetch_like.url,
+                        method: fetch_like.method,
+                        source_kind: fetch_like.source_kind,
+                        span: self.span_file_lines(c.span),
+                    });
+                    return;
+                }
+                // `require("y")` used mid-expression (not bound via a top-level
+                // `const x = require(...)`, e.g. passed straight into a call or
+                // property access) — still worth surfacing as an import boundary.
+                if let Some(src) = match_require_call(expr) {
+                    let referrer = self.current_file();
+                    let (resolved, _) = self.resolver.resolve(&src, &referrer);
+                    chain.push(ProvNode::Import {
+                        source: src,
+                        resolved,
+                        imported: "*".into(),
+                        span: self.span_file_lines(c.span),
+                    });
+                    return;
+                }
+                // navigator.sendBeacon(url, data) — a fire-and-forget fetch-like send.
+                if let Callee::Expr(callee_expr) = &c.callee {
+                    if let Expr::Member(m) = &**callee_expr {
+                        if let (Expr::Ident(obj), MemberProp::Ident(prop)) = (&*m.obj, &m.prop) {
+                            if obj.sym.as_ref() == "navigator" && prop.sym.as_ref() == "sendBeacon" {
+                                let url = c.args.get(0).and_then(|a| static_url_literal(&a.expr));
+                                chain.push(ProvNode::Fetch {
+                                    url,
+                                    method: "POST".to_string(),
+                                    source_kind: "sendBeacon",
+                                    span: self.span_file_lines(c.span),
+                                });
+                                return;
+                            }
+                        }
+                    }
+                    if let Some((root, path)) = self.static_member_path(callee_expr) {
+                        if self.match_runtime_global(&root) {
+                            chain.push(ProvNode::Runtime {
+                                api: format!("{}{}", root, path),
+                                span: self.span_file_lines(c.span),
+                            });
+                            return;
+                        }
+                    }
+                }
                 let (mut callee_name, callee_span, fn_def_span) = match &c.callee {
                     Callee::Expr(expr) => match &**expr {
                         Expr::Ident(id) => {
@@ -703,6 +1560,38 @@ impl CodePressTransform {
                 }
             }
             Expr::New(n) => {
+                if let Expr::Ident(id) = &*n.callee {
+                    if id.sym.as_ref() == "XMLHttpRequest" {
+                        chain.push(ProvNode::Fetch {
+                            url: None, // set later via .open(method, url), not visible at the `new` site
+                            method: "UNKNOWN".to_string(),
+                            source_kind: "xhr",
+                            span: self.span_file_lines(n.span),
+                        });
+                        if let Some(args) = &n.args {
+                            for arg in args {
+                                if arg.spread.is_none() {
+                                    self.trace_expr(&arg.expr, chain, depth + 1, seen);
+                                }
+                            }
+                        }
+                        return;
+                    }
+                    if self.match_runtime_global(id.sym.as_ref()) {
+                        chain.push(ProvNode::Runtime {
+                            api: format!("new {}", id.sym),
+                            span: self.span_file_lines(n.span),
+                        });
+                        if let Some(args) = &n.args {
+                            for arg in args {
+                                if arg.spread.is_none() {
+                                    self.trace_expr(&arg.expr, chain, depth + 1, seen);
+                                }
+                            }
+                        }
+                        return;
+                    }
+                }
                 let callee = match &*n.callee {
                     Expr::Ident(id) => id.sym.to_string(),
                     Expr::Member(_) => "<member>".to_string(),
@@ -795,9 +1684,47 @@ impl CodePressTransform {
         }
     }
 
+    /// Follows a re-export chain through `imported_graphs` (host-supplied facts
+    /// about modules this plugin already processed elsewhere) until it lands on
+    /// a concrete definition, or bails after `MAX_REEXPORT_HOPS` — whichever
+    /// comes first. A `(module, name)` seen-set breaks cycles from diamond
+    /// barrel re-exports. Returns `None` when `start_module` wasn't supplied by
+    /// the host, which is the common case when `importedModuleGraphs` isn't
+    /// wired up — the chain then simply terminates at `ProvNode::Import`.
+    fn resolve_cross_module(&self, start_module: &str, start_name: &str) -> Option<ProvNode> {
+        let mut module = start_module.to_string();
+        let mut name = start_name.to_string();
+        let mut seen: HashSet<(String, String)> = HashSet::new();
+        for hop in 0..MAX_REEXPORT_HOPS {
+            if !seen.insert((module.clone(), name.clone())) {
+                return None; // diamond re-export cycle
+            }
+            let facts = self.imported_graphs.get(&module)?;
+            if let Some((local, kind)) = facts.exports.get(&name) {
+                return Some(ProvNode::RemoteDef {
+                    module,
+                    local: local.clone(),
+                    def_kind: kind.clone(),
+                    hops: hop,
+                });
+            }
+            match facts.reexports.get(&name) {
+                Some((next_module, next_name)) => {
+                    module = next_module.clone();
+                    name = next_name.clone();
+                }
+                None => return None,
+            }
+        }
+        None
+    }
+
     fn rank_candidates(&self, chain: &[ProvNode]) -> Vec<Candidate> {
         let mut out: Vec<Candidate> = vec![];
         for n in chain {
+            if !self.kind_allowed(prov_node_kind(n)) {
+                continue;
+            }
             match n {
                 ProvNode::Literal { span, .. } => out.push(Candidate {
                     target: span.clone(),
@@ -849,6 +1776,14 @@ impl CodePressTransform {
                     target: span.clone(),
                     reason: "fetch".into(),
                 }),
+                ProvNode::RemoteDef { module, local, .. } => out.push(Candidate {
+                    target: format!("{}#{}", module, local),
+                    reason: "remote-def".into(),
+                }),
+                ProvNode::Runtime { span, .. } => out.push(Candidate {
+                    target: span.clone(),
+                    reason: "runtime-global".into(),
+                }),
                 _ => {}
             }
         }
@@ -858,19 +1793,40 @@ impl CodePressTransform {
             .filter(|c| seen.insert(format!("{}#{}", c.reason, c.target)))
             .collect()
     }
+    /// Resolves a `SymbolRef`'s `local` binding to where it actually comes from: an import's
+    /// `module_specifier`/`imported_name` (following `Binding::import`, already populated by
+    /// `BindingCollector` for every `ImportDecl`/CommonJS-`require` form), or the in-file span it
+    /// was defined at. `None` when `id` isn't a binding this module collected at all.
+    fn symbol_origin(&self, id: &Id) -> Option<SymbolOrigin> {
+        let b = self.bindings.get(id)?;
+        Some(match &b.import {
+            Some(info) => SymbolOrigin::Import {
+                module_specifier: info.source.clone(),
+                resolved: info.resolved.clone(),
+                imported_name: info.imported.clone(),
+                is_default: info.imported == "default",
+                is_namespace: info.imported == "*",
+            },
+            None => SymbolOrigin::Local {
+                def_span: self.span_file_lines(b.def_span),
+            },
+        })
+    }
+
     fn collect_symbol_refs_from_expr(&mut self, expr: &Expr, out: &mut Vec<SymbolRef>) {
         // Remember file as soon as we can
         let _ = self.file_from_span(expr.span());
         match expr {
             Expr::Ident(i) => {
+                let id = i.to_id();
                 out.push(SymbolRef {
                     file: self.current_file(),
                     local: i.sym.to_string(),
                     path: "".to_string(),
                     span: self.span_file_lines(i.span),
+                    origin: self.symbol_origin(&id),
                 });
                 // 2) chase initialier (for mutated imports that are re-exported)
-                let id = i.to_id();
                 let init_expr: Option<Expr> = self
                     .bindings
                     .get(&id)
@@ -882,11 +1838,13 @@ impl CodePressTransform {
             }
             Expr::Member(m) => {
                 if let Some((root, path)) = self.static_member_path(&Expr::Member(m.clone())) {
+                    let origin = member_root_ident(&m.obj).and_then(|i| self.symbol_origin(&i.to_id()));
                     out.push(SymbolRef {
                         file: self.current_file(),
                         local: root,
                         path,
                         span: self.span_file_lines(m.span),
+                        origin,
                     });
                 }
                 // also descend into obj/prop expr for nested refs
@@ -909,31 +1867,25 @@ impl CodePressTransform {
         }
     }
 
-    fn aggregate_kinds(chain: &[ProvNode]) -> Vec<&'static str> {
+    fn aggregate_kinds(&self, chain: &[ProvNode]) -> Vec<&'static str> {
         let mut kinds = BTreeSet::new();
         for n in chain {
-            let k = match n {
-                ProvNode::Literal { .. } => "literal",
-                ProvNode::Ident { .. } => "ident",
-                ProvNode::Init { .. } => "init",
-                ProvNode::Import { .. } => "import",
-                ProvNode::Member { .. } => "member",
-                ProvNode::ObjectProp { .. } => "object",
-                ProvNode::ArrayElem { .. } => "array",
-                ProvNode::Call { .. } => "call",
-                ProvNode::Ctor { .. } => "ctor",
-                ProvNode::Op { .. } => "op",
-                ProvNode::Env { .. } => "env",
-                ProvNode::Fetch { .. } => "fetch",
-                ProvNode::Context { .. } => "context",
-                ProvNode::Hook { .. } => "hook",
-                ProvNode::Unknown { .. } => "unknown",
-            };
-            kinds.insert(k);
+            let k = prov_node_kind(n);
+            if self.kind_allowed(k) {
+                kinds.insert(k);
+            }
         }
         kinds.into_iter().collect()
     }
 
+    // `config.provNodeKinds` narrows both `aggregate_kinds` and `rank_candidates` to a caller
+    // chosen subset; unset means no filtering (the historical, unrestricted behavior).
+    fn kind_allowed(&self, kind: &str) -> bool {
+        self.prov_node_kinds
+            .as_ref()
+            .map_or(true, |allow| allow.contains(kind))
+    }
+
     // Build a <codepress-marker style={{display:'contents'}} ...> wrapper with callsite
     fn make_display_contents_wrapper(
         &self,
@@ -1012,7 +1964,7 @@ impl CodePressTransform {
             span: DUMMY_SP,
             specifiers: vec![ImportSpecifier::Named(ImportNamedSpecifier {
                 span: DUMMY_SP,
-                local: cp_ident("createContext".into()),
+                local: cp_ident(PROVIDER_IMPORT_LOCAL),
                 imported: None,
                 is_type_only: false,
             })],
@@ -1040,7 +1992,7 @@ impl CodePressTransform {
                 }),
                 init: Some(Box::new(Expr::Call(CallExpr {
                     span: DUMMY_SP,
-                    callee: Callee::Expr(Box::new(Expr::Ident(cp_ident("createContext".into())))),
+                    callee: Callee::Expr(Box::new(Expr::Ident(cp_ident(PROVIDER_IMPORT_LOCAL)))),
                     args: vec![ExprOrSpread {
                         spread: None,
                         expr: Box::new(Expr::Lit(Lit::Null(Null { span: DUMMY_SP }))),
@@ -1160,43 +2112,195 @@ impl CodePressTransform {
         };
 
         // Insert after any top-of-file directives, preserving order: import, const, displayName, function
-        let insert_at = self.directive_insert_index(m);
+        let insert_at = directive_insert_index(m);
         // Insert in reverse so the final order is preserved
         m.body.insert(insert_at, provider_fn);
         m.body.insert(insert_at, cpx_name_stmt);
         m.body.insert(insert_at, cpx_decl);
         m.body.insert(insert_at, import_decl);
         self.inserted_provider_import = true;
+        self.helper_item_count += 4;
     }
 
-    /// Injects a guarded stamping helper:
-    /// function __CP_stamp(v,id,fp){try{if(v&&(typeof v==='function'||typeof v==='object')&&Object.isExtensible(v)){v.__cp_id=id;v.__cp_fp=fp;}}catch(_){}return v;}
+    /// Injects a guarded stamping helper as a real hoisted declaration — `new Function(...)`
+    /// is blocked outright under a CSP without `unsafe-eval`, which silently disabled stamping
+    /// on strict-CSP sites. Builds the equivalent AST directly instead:
+    /// function __CP_stamp(v,id,fp){try{if(v&&(typeof v==='function'||typeof v==='object')&&Object.isExtensible(v)){v.__cp_id=id;v.__cp_fp=fp;}}catch(_e){}return v;}
+    /// globalThis.__CP_stamp = globalThis.__CP_stamp || __CP_stamp;
     fn ensure_stamp_helper_inline(&mut self, m: &mut Module) {
         if self.inserted_stamp_helper {
             return;
         }
-        // Inject helper via a small runtime snippet executed with new Function
-        let js = "try{var g=(typeof globalThis!=='undefined'?globalThis:window);if(!g.__CP_stamp)g.__CP_stamp=function(v,id,fp){try{if(v&&(typeof v==='function'||typeof v==='object')&&Object.isExtensible(v)){v.__cp_id=id;v.__cp_fp=fp;}}catch(_e){}return v;}}catch(_e){}";
-        let stmt = ModuleItem::Stmt(Stmt::Expr(ExprStmt {
-            span: DUMMY_SP,
-            expr: Box::new(Expr::Call(CallExpr {
+
+        fn typeof_eq(name: &str, value: &str) -> Expr {
+            Expr::Bin(BinExpr {
                 span: DUMMY_SP,
-                callee: Callee::Expr(Box::new(Expr::New(NewExpr {
+                op: BinaryOp::EqEqEq,
+                left: Box::new(Expr::Unary(UnaryExpr {
                     span: DUMMY_SP,
-                    callee: Box::new(Expr::Ident(cp_ident("Function".into()))),
-                    args: Some(vec![ExprOrSpread { spread: None, expr: Box::new(Expr::Lit(Lit::Str(Str { span: DUMMY_SP, value: js.into(), raw: None })) ) }]),
-                    type_args: None,
+                    op: UnaryOp::TypeOf,
+                    arg: Box::new(Expr::Ident(cp_ident(name))),
+                })),
+                right: Box::new(Expr::Lit(Lit::Str(Str {
+                    span: DUMMY_SP,
+                    value: value.into(),
+                    raw: None,
+                }))),
+            })
+        }
+
+        // v && (typeof v === 'function' || typeof v === 'object') && Object.isExtensible(v)
+        let is_fn_or_object = Expr::Bin(BinExpr {
+            span: DUMMY_SP,
+            op: BinaryOp::LogicalOr,
+            left: Box::new(typeof_eq("v", "function")),
+            right: Box::new(typeof_eq("v", "object")),
+        });
+        let is_extensible_call = Expr::Call(CallExpr {
+            span: DUMMY_SP,
+            callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
+                span: DUMMY_SP,
+                obj: Box::new(Expr::Ident(cp_ident("Object"))),
+                prop: MemberProp::Ident(cp_ident_name("isExtensible")),
+            }))),
+            args: vec![ExprOrSpread {
+                spread: None,
+                expr: Box::new(Expr::Ident(cp_ident("v"))),
+            }],
+            type_args: None,
+            #[cfg(not(feature = "compat_0_87"))]
+            ctxt: SyntaxContext::empty(),
+        });
+        let guard_test = Expr::Bin(BinExpr {
+            span: DUMMY_SP,
+            op: BinaryOp::LogicalAnd,
+            left: Box::new(Expr::Bin(BinExpr {
+                span: DUMMY_SP,
+                op: BinaryOp::LogicalAnd,
+                left: Box::new(Expr::Ident(cp_ident("v"))),
+                right: Box::new(is_fn_or_object),
+            })),
+            right: Box::new(is_extensible_call),
+        });
+
+        fn stamp_field_assign(field: &str, value_ident: &str) -> Stmt {
+            Stmt::Expr(ExprStmt {
+                span: DUMMY_SP,
+                expr: Box::new(Expr::Assign(AssignExpr {
+                    span: DUMMY_SP,
+                    op: AssignOp::Assign,
+                    left: make_assign_left_member(Expr::Ident(cp_ident("v")), cp_ident_name(field)),
+                    right: Box::new(Expr::Ident(cp_ident(value_ident))),
+                })),
+            })
+        }
+
+        let guarded_if = Stmt::If(IfStmt {
+            span: DUMMY_SP,
+            test: Box::new(guard_test),
+            cons: Box::new(Stmt::Block(BlockStmt {
+                span: DUMMY_SP,
+                stmts: vec![
+                    stamp_field_assign("__cp_id", "id"),
+                    stamp_field_assign("__cp_fp", "fp"),
+                ],
+                #[cfg(not(feature = "compat_0_87"))]
+                ctxt: SyntaxContext::empty(),
+            })),
+            alt: None,
+        });
+
+        let try_stmt = Stmt::Try(Box::new(TryStmt {
+            span: DUMMY_SP,
+            block: BlockStmt {
+                span: DUMMY_SP,
+                stmts: vec![guarded_if],
+                #[cfg(not(feature = "compat_0_87"))]
+                ctxt: SyntaxContext::empty(),
+            },
+            handler: Some(CatchClause {
+                span: DUMMY_SP,
+                param: Some(Pat::Ident(BindingIdent {
+                    id: cp_ident("_e"),
+                    type_ann: None,
+                })),
+                body: BlockStmt {
+                    span: DUMMY_SP,
+                    stmts: vec![],
                     #[cfg(not(feature = "compat_0_87"))]
                     ctxt: SyntaxContext::empty(),
-                }))),
-                args: vec![],
-                type_args: None,
+                },
+            }),
+            finalizer: None,
+        }));
+
+        let return_v = Stmt::Return(ReturnStmt {
+            span: DUMMY_SP,
+            arg: Some(Box::new(Expr::Ident(cp_ident("v")))),
+        });
+
+        let params = ["v", "id", "fp"]
+            .iter()
+            .map(|name| Param {
+                span: DUMMY_SP,
+                decorators: vec![],
+                pat: Pat::Ident(BindingIdent {
+                    id: cp_ident(name),
+                    type_ann: None,
+                }),
+            })
+            .collect();
+
+        let stamp_fn = ModuleItem::Stmt(Stmt::Decl(Decl::Fn(FnDecl {
+            ident: cp_ident("__CP_stamp"),
+            declare: false,
+            function: Box::new(Function {
+                params,
+                decorators: vec![],
+                span: DUMMY_SP,
+                body: Some(BlockStmt {
+                    span: DUMMY_SP,
+                    stmts: vec![try_stmt, return_v],
+                    #[cfg(not(feature = "compat_0_87"))]
+                    ctxt: SyntaxContext::empty(),
+                }),
+                is_generator: false,
+                is_async: false,
+                type_params: None,
+                return_type: None,
                 #[cfg(not(feature = "compat_0_87"))]
                 ctxt: SyntaxContext::empty(),
+            }),
+        })));
+
+        // globalThis.__CP_stamp = globalThis.__CP_stamp || __CP_stamp;
+        // A plain assignment, not a declaration guard, since `__CP_stamp` above is
+        // hoisted fresh in every module that injects it — this just keeps a single
+        // shared reference reachable off `globalThis` for any external introspection.
+        let install_stmt = ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+            span: DUMMY_SP,
+            expr: Box::new(Expr::Assign(AssignExpr {
+                span: DUMMY_SP,
+                op: AssignOp::Assign,
+                left: make_assign_left_member(Expr::Ident(cp_ident("globalThis")), cp_ident_name("__CP_stamp")),
+                right: Box::new(Expr::Bin(BinExpr {
+                    span: DUMMY_SP,
+                    op: BinaryOp::LogicalOr,
+                    left: Box::new(Expr::Member(MemberExpr {
+                        span: DUMMY_SP,
+                        obj: Box::new(Expr::Ident(cp_ident("globalThis"))),
+                        prop: MemberProp::Ident(cp_ident_name("__CP_stamp")),
+                    })),
+                    right: Box::new(Expr::Ident(cp_ident("__CP_stamp"))),
+                })),
             })),
         }));
-        m.body.insert(0, stmt);
+
+        let insert_at = directive_insert_index(m);
+        m.body.insert(insert_at, install_stmt);
+        m.body.insert(insert_at, stamp_fn);
         self.inserted_stamp_helper = true;
+        self.helper_item_count += 2;
     }
 }
 
@@ -1208,7 +2312,8 @@ impl CodePressTransform {
 struct ImportRow {
     local: String,    // local alias in this module
     imported: String, // 'default' | named | '*' (namespace)
-    source: String,   // "…/module"
+    source: String,   // "…/module", exactly as written
+    resolved: String, // canonicalized via `Resolver::resolve`
     span: String,     // "file:start-end"
 }
 
@@ -1216,6 +2321,11 @@ struct ImportRow {
 struct ExportRow {
     exported: String, // name visible to other modules ('default' is ok)
     local: String,    // local symbol bound in this module
+    // The `local` binding's hygiene-aware `Id`, so `export { user as default }` links to the
+    // exact (possibly shadowed) binding instead of whichever `user` a string match happens to
+    // find first. `None` when the export names no local binding: an anonymous `export default`,
+    // or a CommonJS `module.exports.x = <expr>` assignment whose right side isn't an identifier.
+    local_id: Option<(String, u32)>,
     span: String,
 }
 
@@ -1223,23 +2333,41 @@ struct ExportRow {
 struct ReexportRow {
     exported: String, // name re-exported by this module
     imported: String, // name imported from source
-    source: String,   // "…/module"
+    source: String,   // "…/module", exactly as written
+    resolved: String, // canonicalized via `Resolver::resolve`
     span: String,
 }
 
 #[derive(serde::Serialize)]
 struct DefRow {
-    local: String,      // local binding in this module
+    local: String, // local binding in this module
+    // `local`'s hygiene-aware `Id` (sym + raw `SyntaxContext` index), so two defs that share a
+    // symbol in different lexical scopes (shadowing) don't collide downstream.
+    id: (String, u32),
     kind: &'static str, // var|let|const|func|class
     span: String,
+    scope: usize, // id into `ModuleGraph::scopes`
 }
 
 #[derive(serde::Serialize)]
 struct MutationRow {
     root: String,       // root local ident being mutated (teams)
+    // `root`'s hygiene-aware `Id` when the root is a plain identifier target (`x = ...`,
+    // `x++`). `None` for member-expression roots resolved through `static_member_path`, which
+    // doesn't track the root `Ident`'s `SyntaxContext`.
+    root_id: Option<(String, u32)>,
     path: String,       // dotted/index path if static: ".new_key" or '["k"]' or "[2]"
     kind: &'static str, // assign|update|call:Object.assign|call:push|call:set|spread-merge
     span: String,
+    scope: usize, // id into `ModuleGraph::scopes`
+}
+
+#[derive(serde::Serialize)]
+struct ScopeRow {
+    id: usize,
+    parent: Option<usize>,
+    kind: &'static str, // module|function|arrow|block
+    span: String,
 }
 
 #[derive(serde::Serialize)]
@@ -1248,6 +2376,36 @@ struct LiteralIxRow {
     path: String,        // e.g. [1].specialty
     text: String,
     span: String,
+    kind: &'static str, // "string" | "template" | "jsx-text" — how to round-trip an edit back into the node
+}
+
+/// Why an export wasn't stamped with `__CP_stamp`, mirroring Parcel's `Bailout`/`BailoutReason`
+/// so tooling can explain "this component won't be editable because…" instead of the export
+/// just silently not showing up. Unit variants serialize to their own name (`"NotStampable"`,
+/// …), so the reason string downstream tooling sees is exactly the variant name below.
+#[derive(Clone, Copy, serde::Serialize)]
+enum BailoutReason {
+    /// Failed `CodePressTransform::is_stampable` — not PascalCase under the default predicate,
+    /// excluded by a custom `stampPredicate` mode/allow-list, or `enableStamping` is off.
+    NotStampable,
+    UnsafeInitializer,
+    NoInitializer,
+    ReexportBindingNotFound,
+    DefaultUnnamed,
+}
+
+#[derive(serde::Serialize)]
+struct BailoutRow {
+    export: String, // export (or local) name the decision was made about
+    span: String,
+    reason: BailoutReason,
+}
+
+#[derive(serde::Serialize)]
+struct DynImportRow {
+    source: String, // "./Foo", exactly as written — not run through `Resolver::resolve`, same as `ImportRow::source`
+    span: String,
+    awaited: bool, // true for `await import(...)` / `import(...).then(...)`, false for a bare `import(...)` expression
 }
 
 #[derive(serde::Serialize)]
@@ -1258,6 +2416,39 @@ struct ModuleGraph {
     defs: Vec<DefRow>,
     mutations: Vec<MutationRow>,
     literal_index: Vec<LiteralIxRow>,
+    scopes: Vec<ScopeRow>,
+    bailouts: Vec<BailoutRow>,
+    // Dynamic `import()` call sites — a module edge, same as `imports`, but expression-shaped
+    // rather than a declaration, so it lives in its own row kind instead of forcing `ImportRow`
+    // to grow an `Option<bool>` for "was this static".
+    dyn_imports: Vec<DynImportRow>,
+    // The transitive closure of `exports`/`reexports`: where each externally-visible export
+    // name is actually defined, following `export { x } from '...'`/`export * from '...'` edges
+    // to their origin instead of stopping at the next hop. Built by `resolve_exports`.
+    resolved_exports: Vec<ResolvedExportRow>,
+    // One row per instrumented callsite, keyed by the same encoded id already embedded in the
+    // `codepress-data-fp`/`data-codepress-callsite` attrs, so an editor overlay can resolve a
+    // click straight to a position (and its resolved symbol refs) without reparsing the file.
+    span_map: Vec<SpanMapRow>,
+}
+
+#[derive(serde::Serialize)]
+struct SpanMapRow {
+    callsite_id: String,
+    file: String,
+    start_line: usize,
+    start_col: usize,
+    end_line: usize,
+    end_col: usize,
+    symbol_refs: Vec<SymbolRef>,
+}
+
+#[derive(serde::Serialize)]
+struct ResolvedExportRow {
+    exported: String,            // name visible to consumers of this module
+    file: String,                // resolved path where `local_symbol` is actually defined
+    local_symbol: String,        // local binding name in `file`
+    kind: Option<String>,        // def kind ("const"|"func"|"class"|...) if known
 }
 
 // -----------------------------------------------------------------------------
@@ -1275,9 +2466,26 @@ struct Binding {
 #[derive(Clone)]
 struct ImportInfo {
     source: String,
+    resolved: String,
     imported: String,
 }
 
+/// Export facts for one module other than the one currently being transformed,
+/// keyed by its `Resolver::resolve`d path. The plugin runs one module per
+/// invocation with no filesystem access, so it can never parse a sibling module
+/// itself — a host that already ran this same transform across the project can
+/// feed back each module's export table here (`importedModuleGraphs` config key)
+/// so provenance tracing can hop across the import boundary instead of stopping
+/// at it. Absent entries just mean the chain terminates at `ProvNode::Import`,
+/// same as before this existed.
+#[derive(Clone, Default)]
+struct RemoteModuleFacts {
+    // exported name -> (local name in that module, def kind if known)
+    exports: HashMap<String, (String, Option<String>)>,
+    // exported name -> (resolved source module, imported name), for `export { x } from '...'`
+    reexports: HashMap<String, (String, String)>,
+}
+
 #[derive(serde::Serialize)]
 #[serde(tag = "kind")]
 enum ProvNode {
@@ -1294,6 +2502,7 @@ enum ProvNode {
     },
     Import {
         source: String,
+        resolved: String,
         imported: String,
         span: String,
     },
@@ -1328,6 +2537,8 @@ enum ProvNode {
     },
     Fetch {
         url: Option<String>,
+        method: String,
+        source_kind: &'static str,
         span: String,
     },
     Context {
@@ -1338,9 +2549,31 @@ enum ProvNode {
         name: String,
         span: String,
     },
+    // A well-known runtime/Web/DOM global (`localStorage`, `Date.now`, `new AbortController()`, …)
+    // matched against the `runtime_globals` catalog. See `CodePressTransform::match_runtime_global`.
+    Runtime {
+        api: String,
+        span: String,
+    },
+    // Terminal node reached by following a resolved import past its own module,
+    // via host-supplied `importedModuleGraphs` facts (see `CodePressTransform::resolve_cross_module`).
+    // There's no remote AST to keep tracing into, so this is where the chain bottoms out.
+    RemoteDef {
+        module: String,
+        local: String,
+        def_kind: Option<String>,
+        hops: usize,
+    },
     Unknown {
         span: String,
     },
+    // Chain walk stopped early because it hit `max_trace_depth`, not because the expression
+    // bottomed out. The separate 128-node chain cap in `CodePressTransform::trace_expr` stops
+    // without emitting this node.
+    Truncated {
+        span: String,
+        reason: &'static str,
+    },
 }
 
 #[derive(serde::Serialize)]
@@ -1349,11 +2582,108 @@ struct Candidate {
     reason: String,
 }
 
+// The `kind` tag `ProvNode`'s own `#[serde(tag = "kind")]` would serialize, computed without
+// actually serializing — shared by `aggregate_kinds` and `rank_candidates` so `provNodeKinds`
+// filtering stays in one place instead of two parallel matches drifting apart.
+fn prov_node_kind(n: &ProvNode) -> &'static str {
+    match n {
+        ProvNode::Literal { .. } => "literal",
+        ProvNode::Ident { .. } => "ident",
+        ProvNode::Init { .. } => "init",
+        ProvNode::Import { .. } => "import",
+        ProvNode::Member { .. } => "member",
+        ProvNode::ObjectProp { .. } => "object",
+        ProvNode::ArrayElem { .. } => "array",
+        ProvNode::Call { .. } => "call",
+        ProvNode::Ctor { .. } => "ctor",
+        ProvNode::Op { .. } => "op",
+        ProvNode::Env { .. } => "env",
+        ProvNode::Fetch { .. } => "fetch",
+        ProvNode::Context { .. } => "context",
+        ProvNode::Hook { .. } => "hook",
+        ProvNode::Runtime { .. } => "runtime",
+        ProvNode::RemoteDef { .. } => "remote-def",
+        ProvNode::Unknown { .. } => "unknown",
+        ProvNode::Truncated { .. } => "truncated",
+    }
+}
+
 struct BindingCollector<'a> {
     out: &'a mut HashMap<Id, Binding>,
+    resolver: &'a Resolver,
+    referrer_file: &'a str,
 }
 impl<'a> Visit for BindingCollector<'a> {
     fn visit_var_declarator(&mut self, d: &VarDeclarator) {
+        // CommonJS interop: `const x = require("y")` / `const { a, b } = require("y")`.
+        // There's no specifier list to walk like an ImportDecl, so bind directly off the pattern.
+        if let Some(src) = d.init.as_deref().and_then(match_require_call) {
+            let resolved = self.resolver.resolve(&src, self.referrer_file).0;
+            match &d.name {
+                Pat::Ident(name) => {
+                    self.out.insert(
+                        name.to_id(),
+                        Binding {
+                            def_span: name.id.span,
+                            init: None,
+                            import: Some(ImportInfo {
+                                source: src,
+                                resolved,
+                                imported: "*".into(),
+                            }),
+                            fn_body_span: None,
+                        },
+                    );
+                }
+                Pat::Object(obj) => {
+                    for prop in &obj.props {
+                        match prop {
+                            ObjectPatProp::KeyValue(kv) => {
+                                if let Some(local) = kv.value.as_ident() {
+                                    let imported = match &kv.key {
+                                        PropName::Ident(i) => i.sym.to_string(),
+                                        PropName::Str(s) => s.value.to_string(),
+                                        _ => continue,
+                                    };
+                                    self.out.insert(
+                                        local.to_id(),
+                                        Binding {
+                                            def_span: local.id.span,
+                                            init: None,
+                                            import: Some(ImportInfo {
+                                                source: src.clone(),
+                                                resolved: resolved.clone(),
+                                                imported,
+                                            }),
+                                            fn_body_span: None,
+                                        },
+                                    );
+                                }
+                            }
+                            ObjectPatProp::Assign(a) => {
+                                self.out.insert(
+                                    a.key.to_id(),
+                                    Binding {
+                                        def_span: a.key.id.span,
+                                        init: None,
+                                        import: Some(ImportInfo {
+                                            source: src.clone(),
+                                            resolved: resolved.clone(),
+                                            imported: a.key.id.sym.to_string(),
+                                        }),
+                                        fn_body_span: None,
+                                    },
+                                );
+                            }
+                            ObjectPatProp::Rest(_) => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+            d.visit_children_with(self);
+            return;
+        }
         if let Some(name) = d.name.as_ident() {
             self.out.insert(
                 name.to_id(),
@@ -1402,6 +2732,7 @@ impl<'a> Visit for BindingCollector<'a> {
                             init: None,
                             import: Some(ImportInfo {
                                 source: n.src.value.to_string(),
+                                resolved: self.resolver.resolve(&n.src.value, self.referrer_file).0,
                                 imported,
                             }),
                             fn_body_span: None,
@@ -1416,6 +2747,7 @@ impl<'a> Visit for BindingCollector<'a> {
                             init: None,
                             import: Some(ImportInfo {
                                 source: n.src.value.to_string(),
+                                resolved: self.resolver.resolve(&n.src.value, self.referrer_file).0,
                                 imported: "default".into(),
                             }),
                             fn_body_span: None,
@@ -1430,6 +2762,7 @@ impl<'a> Visit for BindingCollector<'a> {
                             init: None,
                             import: Some(ImportInfo {
                                 source: n.src.value.to_string(),
+                                resolved: self.resolver.resolve(&n.src.value, self.referrer_file).0,
                                 imported: "*".into(),
                             }),
                             fn_body_span: None,
@@ -1446,6 +2779,63 @@ impl<'a> Visit for BindingCollector<'a> {
 // Detectors
 // -----------------------------------------------------------------------------
 
+/// Matches `require("some/source")` — the one CommonJS shape worth special-casing,
+/// since the callee is always the unresolved global `require` with a single string
+/// literal argument. Returns the literal specifier, unresolved.
+fn match_require_call(expr: &Expr) -> Option<String> {
+    let call = match expr {
+        Expr::Call(c) => c,
+        _ => return None,
+    };
+    let callee = match &call.callee {
+        Callee::Expr(e) => e,
+        _ => return None,
+    };
+    match &**callee {
+        Expr::Ident(id) if id.sym.as_ref() == "require" => {}
+        _ => return None,
+    }
+    match call.args.first() {
+        Some(arg) if arg.spread.is_none() => match &*arg.expr {
+            Expr::Lit(Lit::Str(s)) => Some(s.value.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Matches the CommonJS export assignment shapes — `module.exports = …`,
+/// `module.exports.foo = …`, `exports.foo = …` — and returns the exported name
+/// ("default" for the whole-object form). Anything else (a plain member mutation
+/// on some other object) returns `None` so the caller falls back to `push_mutation_row`.
+fn match_commonjs_export(left: &Expr) -> Option<String> {
+    let m = match left {
+        Expr::Member(m) => m,
+        _ => return None,
+    };
+    if let MemberProp::Ident(prop) = &m.prop {
+        match &*m.obj {
+            Expr::Ident(obj) if obj.sym.as_ref() == "exports" => {
+                return Some(prop.sym.to_string());
+            }
+            Expr::Member(inner) => {
+                if let (Expr::Ident(o), MemberProp::Ident(p)) = (&*inner.obj, &inner.prop) {
+                    if o.sym.as_ref() == "module" && p.sym.as_ref() == "exports" {
+                        return Some(prop.sym.to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if let (Expr::Ident(o), MemberProp::Ident(p)) = (&*m.obj, &m.prop) {
+        if o.sym.as_ref() == "module" && p.sym.as_ref() == "exports" {
+            return Some("default".to_string());
+        }
+    }
+    None
+}
+
 fn detect_env_member(m: &MemberExpr) -> Option<String> {
     // process.env.X
     if let Expr::Member(obj) = &*m.obj {
@@ -1475,33 +2865,108 @@ fn detect_env_member(m: &MemberExpr) -> Option<String> {
 
 struct FetchLike {
     url: Option<String>,
+    method: String,
+    source_kind: &'static str,
 }
-fn detect_fetch_like(c: &CallExpr) -> Option<FetchLike> {
-    match &c.callee {
-        Callee::Expr(expr) => match &**expr {
-            Expr::Ident(id) if id.sym.as_ref() == "fetch" => {
-                let url = c.args.get(0).and_then(|a| match &*a.expr {
-                    Expr::Lit(Lit::Str(s)) => Some(s.value.to_string()),
-                    _ => None,
-                });
-                Some(FetchLike { url })
-            }
-            Expr::Member(m) => {
-                if let MemberProp::Ident(prop) = &m.prop {
-                    let p = prop.sym.as_ref();
-                    if ["get", "post", "put", "delete", "query", "mutate", "request"].contains(&p) {
-                        let url = c.args.get(0).and_then(|a| match &*a.expr {
-                            Expr::Lit(Lit::Str(s)) => Some(s.value.to_string()),
-                            _ => None,
-                        });
-                        return Some(FetchLike { url });
+
+/// Names of the common data-fetching hooks whose first arg is (usually) the request URL/key.
+const DATA_HOOK_NAMES: &[&str] = &["useSWR", "useSWRImmutable", "useQuery", "useMutation"];
+
+/// Extract a statically-known string: a plain literal, or a template literal with no
+/// interpolations (only literal quasis).
+fn static_url_literal(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(Lit::Str(s)) => Some(s.value.to_string()),
+        Expr::Tpl(t) if t.exprs.is_empty() => {
+            Some(t.quasis.iter().map(|q| q.raw.as_ref()).collect::<String>())
+        }
+        _ => None,
+    }
+}
+
+/// Pull an HTTP method out of a `fetch(url, { method: "POST" })`-shaped second argument.
+fn method_from_opts_arg(args: &[ExprOrSpread]) -> Option<String> {
+    let opts = &args.get(1)?.expr;
+    let obj = match &**opts {
+        Expr::Object(o) => o,
+        _ => return None,
+    };
+    obj.props.iter().find_map(|p| {
+        let PropOrSpread::Prop(p) = p else { return None };
+        let Prop::KeyValue(kv) = &**p else { return None };
+        let is_method = match &kv.key {
+            PropName::Ident(i) => i.sym.as_ref() == "method",
+            PropName::Str(s) => s.value.as_ref() == "method",
+            _ => false,
+        };
+        if !is_method {
+            return None;
+        }
+        match &*kv.value {
+            Expr::Lit(Lit::Str(s)) => Some(s.value.to_string()),
+            _ => None,
+        }
+    })
+}
+
+impl CodePressTransform {
+    /// Checks a static-member root (or bare `new` callee name) against the
+    /// `runtime_globals` catalog (`BUILTIN_RUNTIME_GLOBALS` plus any host-supplied
+    /// `runtimeGlobals` additions).
+    fn match_runtime_global(&self, root: &str) -> bool {
+        self.runtime_globals.contains(root)
+    }
+
+    /// Recognize `fetch(...)`, axios/ky-style client calls, and common data-fetching hooks so
+    /// `trace_expr` can link a rendered value back to the endpoint that produced it.
+    fn detect_fetch_like(&self, c: &CallExpr) -> Option<FetchLike> {
+        match &c.callee {
+            Callee::Expr(expr) => match &**expr {
+                Expr::Ident(id) if id.sym.as_ref() == "fetch" => {
+                    let url = c.args.get(0).and_then(|a| static_url_literal(&a.expr));
+                    let method = method_from_opts_arg(&c.args).unwrap_or_else(|| "GET".to_string());
+                    Some(FetchLike { url, method, source_kind: "fetch" })
+                }
+                Expr::Ident(id) if DATA_HOOK_NAMES.contains(&id.sym.as_ref()) => {
+                    let url = c.args.get(0).and_then(|a| static_url_literal(&a.expr));
+                    Some(FetchLike {
+                        url,
+                        method: "GET".to_string(),
+                        source_kind: "data-hook",
+                    })
+                }
+                Expr::Member(m) => {
+                    let prop = match &m.prop {
+                        MemberProp::Ident(p) => p.sym.as_ref(),
+                        _ => return None,
+                    };
+                    if !["get", "post", "put", "delete", "patch", "request"].contains(&prop) {
+                        return None;
                     }
+                    let root_is_http_client = match &*m.obj {
+                        Expr::Ident(root) => self
+                            .bindings
+                            .get(&root.to_id())
+                            .and_then(|b| b.import.as_ref())
+                            .map(|im| im.source == "axios" || im.source == "ky")
+                            .unwrap_or(false),
+                        _ => false,
+                    };
+                    if !root_is_http_client {
+                        return None;
+                    }
+                    let url = c.args.get(0).and_then(|a| static_url_literal(&a.expr));
+                    let method = if prop == "request" {
+                        method_from_opts_arg(&c.args).unwrap_or_else(|| "GET".to_string())
+                    } else {
+                        prop.to_uppercase()
+                    };
+                    Some(FetchLike { url, method, source_kind: "axios" })
                 }
-                None
-            }
+                _ => None,
+            },
             _ => None,
-        },
-        _ => None,
+        }
     }
 }
 
@@ -1603,10 +3068,15 @@ impl CodePressTransform {
 
 impl VisitMut for CodePressTransform {
     fn visit_mut_module(&mut self, m: &mut Module) {
+        self.enter_scope("module", m.span);
         // Inject inline provider once per module (from main branch)
-        self.ensure_provider_inline(m);
+        if self.enable_provider {
+            self.ensure_provider_inline(m);
+        }
         // Inject guarded stamping helper
-        self.ensure_stamp_helper_inline(m);
+        if self.enable_stamping {
+            self.ensure_stamp_helper_inline(m);
+        }
 
         // Stamping of exported symbols with __cp_id and __cp_fp (merged change)
         // Determine encoded file path for this module
@@ -1619,33 +3089,6 @@ impl VisitMut for CodePressTransform {
         let normalized = normalize_filename(&filename);
         let encoded_fp = xor_encode(&normalized);
 
-        // Decide whether stamping is safe for an identifier (only for functions/classes/calls/new)
-        let find_binding_by_sym = |sym: &str| -> Option<&Binding> {
-            self.bindings
-                .iter()
-                .find(|(k, _)| k.0 == sym)
-                .map(|(_, b)| b)
-        };
-        let is_pascal = |s: &str| s.chars().next().map(|c| c.is_uppercase()).unwrap_or(false);
-        let should_stamp_ident = |ident: &Ident| -> bool {
-            if !is_pascal(&ident.sym.to_string()) {
-                return false;
-            }
-            if let Some(b) = self.bindings.get(&ident.to_id()) {
-                if let Some(init) = &b.init {
-                    match &**init {
-                        Expr::Fn(_) | Expr::Arrow(_) | Expr::Class(_) | Expr::Call(_) | Expr::New(_) => true,
-                        _ => false,
-                    }
-                } else {
-                    // No initializer (likely handled as Decl::Fn/Class elsewhere)
-                    false
-                }
-            } else {
-                false
-            }
-        };
-
         // Helper to build assignment: Ident.__cp_id = "..." and Ident.__cp_fp = "..."
         let mut stamp_for_ident = |ident: &Ident, export_name: &str| -> Vec<ModuleItem> {
             let mut out: Vec<ModuleItem> = Vec::new();
@@ -1680,13 +3123,13 @@ impl VisitMut for CodePressTransform {
                     match decl {
                         Decl::Fn(fn_decl) => {
                             let name = fn_decl.ident.clone();
-                            if is_pascal(&name.sym.to_string()) {
+                            if self.is_stampable(&name.sym.to_string()) {
                                 new_body.extend(stamp_for_ident(&name, &name.sym.to_string()));
                             }
                         }
                         Decl::Class(class_decl) => {
                             let name = class_decl.ident.clone();
-                            if is_pascal(&name.sym.to_string()) {
+                            if self.is_stampable(&name.sym.to_string()) {
                                 new_body.extend(stamp_for_ident(&name, &name.sym.to_string()));
                             }
                         }
@@ -1694,8 +3137,34 @@ impl VisitMut for CodePressTransform {
                             for d in &var_decl.decls {
                                 if let Pat::Ident(bi) = &d.name {
                                     let name = bi.id.clone();
-                                    if should_stamp_ident(&name) {
-                                        new_body.extend(stamp_for_ident(&name, &name.sym.to_string()));
+                                    let export = name.sym.to_string();
+                                    if !self.is_stampable(&export) {
+                                        // Not a component-shaped export; no bailout, same as before.
+                                        continue;
+                                    }
+                                    let span = self.span_file_lines(name.span);
+                                    match self.bindings.get(&name.to_id()).map(|b| &b.init) {
+                                        Some(Some(init)) => match &**init {
+                                            Expr::Fn(_) | Expr::Arrow(_) | Expr::Class(_) | Expr::Call(_) | Expr::New(_) => {
+                                                new_body.extend(stamp_for_ident(&name, &export));
+                                            }
+                                            _ => {
+                                                self.graph.bailouts.push(BailoutRow {
+                                                    export,
+                                                    span,
+                                                    reason: BailoutReason::UnsafeInitializer,
+                                                });
+                                            }
+                                        },
+                                        // No initializer, or (shouldn't happen — `collect_bindings`
+                                        // already walked this declarator) no binding at all.
+                                        _ => {
+                                            self.graph.bailouts.push(BailoutRow {
+                                                export,
+                                                span,
+                                                reason: BailoutReason::NoInitializer,
+                                            });
+                                        }
                                     }
                                 }
                             }
@@ -1703,19 +3172,40 @@ impl VisitMut for CodePressTransform {
                         _ => {}
                     }
                 }
-                ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(ExportDefaultDecl { decl, .. })) => {
+                ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(ed @ ExportDefaultDecl { decl, .. })) => {
                     new_body.push(item.clone());
+                    let default_span = self.span_file_lines(ed.span());
                     match decl {
                         DefaultDecl::Fn(FnExpr { ident: Some(id), .. }) => {
-                            if is_pascal(&id.sym.to_string()) {
-                                new_body.extend(stamp_for_ident(&id, "default"));
+                            if self.is_stampable(&id.sym.to_string()) {
+                                new_body.extend(stamp_for_ident(id, "default"));
+                            } else {
+                                self.graph.bailouts.push(BailoutRow {
+                                    export: "default".to_string(),
+                                    span: self.span_file_lines(id.span),
+                                    reason: BailoutReason::NotStampable,
+                                });
                             }
                         }
                         DefaultDecl::Class(ClassExpr { ident: Some(id), .. }) => {
-                            if is_pascal(&id.sym.to_string()) {
-                                new_body.extend(stamp_for_ident(&id, "default"));
+                            if self.is_stampable(&id.sym.to_string()) {
+                                new_body.extend(stamp_for_ident(id, "default"));
+                            } else {
+                                self.graph.bailouts.push(BailoutRow {
+                                    export: "default".to_string(),
+                                    span: self.span_file_lines(id.span),
+                                    reason: BailoutReason::NotStampable,
+                                });
                             }
                         }
+                        DefaultDecl::Fn(FnExpr { ident: None, .. })
+                        | DefaultDecl::Class(ClassExpr { ident: None, .. }) => {
+                            self.graph.bailouts.push(BailoutRow {
+                                export: "default".to_string(),
+                                span: default_span,
+                                reason: BailoutReason::DefaultUnnamed,
+                            });
+                        }
                         _ => {}
                     }
                 }
@@ -1724,20 +3214,42 @@ impl VisitMut for CodePressTransform {
                     for spec in specifiers {
                         if let ExportSpecifier::Named(ExportNamedSpecifier { orig, .. }) = spec {
                             if let ModuleExportName::Ident(orig_ident) = orig {
-                                // Only stamp PascalCase with a safe initializer
-                                if is_pascal(&orig_ident.sym.to_string()) {
-                                    if let Some(b) = find_binding_by_sym(&orig_ident.sym.to_string()) {
-                                    let safe = match &b.init {
+                                let export = orig_ident.sym.to_string();
+                                if !self.is_stampable(&export) {
+                                    continue;
+                                }
+                                let span = self.span_file_lines(orig_ident.span);
+                                // Key on the hygiene-aware `Id`, not a symbol string match, so a
+                                // shadowed binding of the same name can't steal this export's slot.
+                                match self.bindings.get(&orig_ident.to_id()) {
+                                    Some(b) => match &b.init {
                                         Some(expr) => match &**expr {
-                                            Expr::Fn(_) | Expr::Arrow(_) | Expr::Class(_) | Expr::Call(_) | Expr::New(_) => true,
-                                            _ => false,
+                                            Expr::Fn(_) | Expr::Arrow(_) | Expr::Class(_) | Expr::Call(_) | Expr::New(_) => {
+                                                let id = cp_ident(&export);
+                                                new_body.extend(stamp_for_ident(&id, &export));
+                                            }
+                                            _ => {
+                                                self.graph.bailouts.push(BailoutRow {
+                                                    export,
+                                                    span,
+                                                    reason: BailoutReason::UnsafeInitializer,
+                                                });
+                                            }
                                         },
-                                        None => false,
-                                    };
-                                        if safe {
-                                            let id = cp_ident(&orig_ident.sym.to_string());
-                                            new_body.extend(stamp_for_ident(&id, &orig_ident.sym.to_string()));
+                                        None => {
+                                            self.graph.bailouts.push(BailoutRow {
+                                                export,
+                                                span,
+                                                reason: BailoutReason::NoInitializer,
+                                            });
                                         }
+                                    },
+                                    None => {
+                                        self.graph.bailouts.push(BailoutRow {
+                                            export,
+                                            span,
+                                            reason: BailoutReason::ReexportBindingNotFound,
+                                        });
                                     }
                                 }
                             }
@@ -1751,10 +3263,35 @@ impl VisitMut for CodePressTransform {
 
         // Continue other transforms and inject graph (from main branch)
         m.visit_mut_children_with(self);
-        self.inject_graph_stmt(m);
+
+        // Flush the callsite `__CP_stamp` calls accumulated while walking JSX elements, inserting
+        // them right after the helper/provider items so those remain first.
+        if !self.pending_callsite_stmts.is_empty() {
+            let insert_at = directive_insert_index(m) + self.helper_item_count;
+            for stmt in self.pending_callsite_stmts.drain(..).rev() {
+                m.body.insert(insert_at, stmt);
+            }
+        }
+
+        if self.emit_module_graph {
+            self.resolve_exports();
+            self.inject_graph_stmt(m);
+        }
+        self.exit_scope();
     }
     fn visit_mut_import_decl(&mut self, n: &mut ImportDecl) {
         let _ = self.file_from_span(n.span);
+        if self.skip_type_only_imports && n.type_only {
+            n.visit_mut_children_with(self);
+            return;
+        }
+        let referrer = self.current_file();
+        let original_source = n.src.value.to_string();
+        let (resolved, is_external) = self.resolver.resolve(&original_source, &referrer);
+        if self.rewrite_import_specifiers && !is_external {
+            n.src.value = resolved.clone().into();
+            n.src.raw = None;
+        }
         for s in &n.specifiers {
             match s {
                 ImportSpecifier::Named(named) => {
@@ -1771,7 +3308,8 @@ impl VisitMut for CodePressTransform {
                                 }
                             })
                             .unwrap_or_else(|| named.local.sym.to_string()),
-                        source: n.src.value.to_string(),
+                        source: original_source.clone(),
+                        resolved: resolved.clone(),
                         span: self.span_file_lines(named.local.span),
                     });
                 }
@@ -1779,7 +3317,8 @@ impl VisitMut for CodePressTransform {
                     self.graph.imports.push(ImportRow {
                         local: def.local.sym.to_string(),
                         imported: "default".into(),
-                        source: n.src.value.to_string(),
+                        source: original_source.clone(),
+                        resolved: resolved.clone(),
                         span: self.span_file_lines(def.local.span),
                     });
                 }
@@ -1787,7 +3326,8 @@ impl VisitMut for CodePressTransform {
                     self.graph.imports.push(ImportRow {
                         local: ns.local.sym.to_string(),
                         imported: "*".into(),
-                        source: n.src.value.to_string(),
+                        source: original_source.clone(),
+                        resolved: resolved.clone(),
                         span: self.span_file_lines(ns.local.span),
                     });
                 }
@@ -1810,27 +3350,34 @@ impl VisitMut for CodePressTransform {
                         // def
                         self.graph.defs.push(DefRow {
                             local: id.id.sym.to_string(),
+                            id: id_pair(&id.id.to_id()),
                             kind: match v.kind {
                                 VarDeclKind::Const => "const",
                                 VarDeclKind::Let => "let",
                                 VarDeclKind::Var => "var",
                             },
                             span: def_span,
+                            scope: self.cur_scope(),
                         });
                         // export mapping
                         self.graph.exports.push(ExportRow {
                             exported: id.id.sym.to_string(),
                             local: id.id.sym.to_string(),
+                            local_id: Some(id_pair(&id.id.to_id())),
                             span: self.span_file_lines(id.id.span), // TODO: should this be a
                                                                     // larger span?
                         });
-                        // literal index (optional): only for simple object/array initializers
-                        if let Some(init) = &d.init {
-                            self.harvest_literal_index(
-                                &id.id.sym.to_string(),
-                                &init,
-                                "".to_string(),
-                            );
+                        // literal index (optional): string/template literals reached through
+                        // object/array nesting, conditional branches and JSX children, and only
+                        // when `config.harvestLiteralIndex` hasn't been turned off.
+                        if self.harvest_literal_index_enabled {
+                            if let Some(init) = &d.init {
+                                self.harvest_literal_index(
+                                    &id.id.sym.to_string(),
+                                    &init,
+                                    "".to_string(),
+                                );
+                            }
                         }
                     }
                 }
@@ -1838,24 +3385,30 @@ impl VisitMut for CodePressTransform {
             Decl::Fn(f) => {
                 self.graph.defs.push(DefRow {
                     local: f.ident.sym.to_string(),
+                    id: id_pair(&f.ident.to_id()),
                     kind: "func",
                     span: self.span_file_lines(f.ident.span),
+                    scope: self.cur_scope(),
                 });
                 self.graph.exports.push(ExportRow {
                     exported: f.ident.sym.to_string(),
                     local: f.ident.sym.to_string(),
+                    local_id: Some(id_pair(&f.ident.to_id())),
                     span: self.span_file_lines(f.ident.span),
                 });
             }
             Decl::Class(c) => {
                 self.graph.defs.push(DefRow {
                     local: c.ident.sym.to_string(),
+                    id: id_pair(&c.ident.to_id()),
                     kind: "class",
                     span: self.span_file_lines(c.ident.span),
+                    scope: self.cur_scope(),
                 });
                 self.graph.exports.push(ExportRow {
                     exported: c.ident.sym.to_string(),
                     local: c.ident.sym.to_string(),
+                    local_id: Some(id_pair(&c.ident.to_id())),
                     span: self.span_file_lines(c.ident.span),
                 });
             }
@@ -1868,7 +3421,17 @@ impl VisitMut for CodePressTransform {
         let _ = self.file_from_span(n.span());
         match n {
             ModuleDecl::ExportNamed(en) => {
-                if let Some(src) = &en.src {
+                if self.skip_type_only_imports && en.type_only {
+                    // `export type { ... } from '...'` has no runtime target to resolve or
+                    // rewrite; leave the specifier untouched and keep it out of the graph.
+                } else if let Some(src) = &mut en.src {
+                    let referrer = self.current_file();
+                    let original_source = src.value.to_string();
+                    let (resolved, is_external) = self.resolver.resolve(&original_source, &referrer);
+                    if self.rewrite_import_specifiers && !is_external {
+                        src.value = resolved.clone().into();
+                        src.raw = None;
+                    }
                     for s in &en.specifiers {
                         if let ExportSpecifier::Named(nm) = s {
                             let imported = match &nm.orig {
@@ -1886,7 +3449,8 @@ impl VisitMut for CodePressTransform {
                             self.graph.reexports.push(ReexportRow {
                                 exported,
                                 imported,
-                                source: src.value.to_string(),
+                                source: original_source.clone(),
+                                resolved: resolved.clone(),
                                 span: self.span_file_lines(en.span),
                             });
                         }
@@ -1907,6 +3471,7 @@ impl VisitMut for CodePressTransform {
                                 self.graph.exports.push(ExportRow {
                                     exported,
                                     local: orig.sym.to_string(),
+                                    local_id: Some(id_pair(&orig.to_id())),
                                     span: self.span_file_lines(orig.span),
                                 });
                             }
@@ -1915,27 +3480,110 @@ impl VisitMut for CodePressTransform {
                 }
             }
             ModuleDecl::ExportAll(ea) => {
-                self.graph.reexports.push(ReexportRow {
-                    exported: "*".into(),
-                    imported: "*".into(),
-                    source: ea.src.value.to_string(),
-                    span: self.span_file_lines(ea.span),
-                });
+                if !(self.skip_type_only_imports && ea.type_only) {
+                    let referrer = self.current_file();
+                    let original_source = ea.src.value.to_string();
+                    let (resolved, is_external) = self.resolver.resolve(&original_source, &referrer);
+                    if self.rewrite_import_specifiers && !is_external {
+                        ea.src.value = resolved.clone().into();
+                        ea.src.raw = None;
+                    }
+                    self.graph.reexports.push(ReexportRow {
+                        exported: "*".into(),
+                        imported: "*".into(),
+                        source: original_source,
+                        resolved,
+                        span: self.span_file_lines(ea.span),
+                    });
+                }
             }
             ModuleDecl::ExportDefaultDecl(ed) => {
-                if let DefaultDecl::Fn(f) = &ed.decl {
-                    if let Some(id) = &f.ident {
-                        self.graph.defs.push(DefRow {
-                            local: id.sym.to_string(),
-                            kind: "func",
-                            span: self.span_file_lines(id.span),
-                        });
-                        self.graph.exports.push(ExportRow {
-                            exported: "default".into(),
-                            local: id.sym.to_string(),
-                            span: self.span_file_lines(ed.span()),
-                        });
+                let default_span = self.span_file_lines(ed.span());
+                match &ed.decl {
+                    DefaultDecl::Fn(f) => {
+                        if let Some(id) = &f.ident {
+                            self.graph.defs.push(DefRow {
+                                local: id.sym.to_string(),
+                                id: id_pair(&id.to_id()),
+                                kind: "func",
+                                span: self.span_file_lines(id.span),
+                                scope: self.cur_scope(),
+                            });
+                            self.graph.exports.push(ExportRow {
+                                exported: "default".into(),
+                                local: id.sym.to_string(),
+                                local_id: Some(id_pair(&id.to_id())),
+                                span: default_span,
+                            });
+                        } else {
+                            // Anonymous `export default function() {}` — no local binding to
+                            // key a def on, but the export itself is still real.
+                            self.graph.exports.push(ExportRow {
+                                exported: "default".into(),
+                                local: "<anonymous>".to_string(),
+                                local_id: None,
+                                span: default_span,
+                            });
+                        }
                     }
+                    DefaultDecl::Class(c) => {
+                        if let Some(id) = &c.ident {
+                            self.graph.defs.push(DefRow {
+                                local: id.sym.to_string(),
+                                id: id_pair(&id.to_id()),
+                                kind: "class",
+                                span: self.span_file_lines(id.span),
+                                scope: self.cur_scope(),
+                            });
+                            self.graph.exports.push(ExportRow {
+                                exported: "default".into(),
+                                local: id.sym.to_string(),
+                                local_id: Some(id_pair(&id.to_id())),
+                                span: default_span,
+                            });
+                        } else {
+                            self.graph.exports.push(ExportRow {
+                                exported: "default".into(),
+                                local: "<anonymous>".to_string(),
+                                local_id: None,
+                                span: default_span,
+                            });
+                        }
+                    }
+                    DefaultDecl::TsInterfaceDecl(_) => {}
+                }
+            }
+            ModuleDecl::ExportDefaultExpr(ed) => {
+                let (local, local_id) = match &*ed.expr {
+                    Expr::Ident(id) => (id.sym.to_string(), Some(id_pair(&id.to_id()))),
+                    _ => ("<anonymous>".to_string(), None),
+                };
+                self.graph.exports.push(ExportRow {
+                    exported: "default".into(),
+                    local,
+                    local_id,
+                    span: self.span_file_lines(ed.span()),
+                });
+
+                // Stamp identifier/arrow default exports by wrapping the expression in place as
+                // `__CP_stamp(<expr>, "<fp>#default", "<fp>")`, so `export default Foo` /
+                // `export default () => {}` carry `__cp_id`/`__cp_fp` like a named export would.
+                if self.stamp_callsites && matches!(&*ed.expr, Expr::Ident(_) | Expr::Arrow(_)) {
+                    let file = self.current_file();
+                    let enc = xor_encode(&file);
+                    let orig = std::mem::replace(&mut ed.expr, Box::new(Expr::Ident(cp_ident("__CP_stamp"))));
+                    ed.expr = Box::new(Expr::Call(CallExpr {
+                        span: DUMMY_SP,
+                        callee: Callee::Expr(Box::new(Expr::Ident(cp_ident("__CP_stamp".into())))),
+                        args: vec![
+                            ExprOrSpread { spread: None, expr: orig },
+                            ExprOrSpread { spread: None, expr: Box::new(Expr::Lit(Lit::Str(Str { span: DUMMY_SP, value: format!("{}#default", enc).into(), raw: None }))) },
+                            ExprOrSpread { spread: None, expr: Box::new(Expr::Lit(Lit::Str(Str { span: DUMMY_SP, value: enc.into(), raw: None }))) },
+                        ],
+                        type_args: None,
+                        #[cfg(not(feature = "compat_0_87"))]
+                        ctxt: SyntaxContext::empty(),
+                    }));
                 }
             }
             _ => {}
@@ -1944,6 +3592,58 @@ impl VisitMut for CodePressTransform {
     }
 
     fn visit_mut_var_declarator(&mut self, d: &mut VarDeclarator) {
+        // CommonJS interop: `const x = require("y")` / `const { a, b } = require("y")`
+        // is an import, not a local def — route it into `graph.imports` like an ImportDecl.
+        if let Some(src) = d.init.as_deref().and_then(match_require_call) {
+            let referrer = self.current_file();
+            let (resolved, _) = self.resolver.resolve(&src, &referrer);
+            match &d.name {
+                Pat::Ident(name) => {
+                    self.graph.imports.push(ImportRow {
+                        local: name.id.sym.to_string(),
+                        imported: "*".into(),
+                        source: src.clone(),
+                        resolved: resolved.clone(),
+                        span: self.span_file_lines(name.id.span),
+                    });
+                }
+                Pat::Object(obj) => {
+                    for prop in &obj.props {
+                        match prop {
+                            ObjectPatProp::KeyValue(kv) => {
+                                if let Some(local) = kv.value.as_ident() {
+                                    let imported = match &kv.key {
+                                        PropName::Ident(i) => i.sym.to_string(),
+                                        PropName::Str(s) => s.value.to_string(),
+                                        _ => continue,
+                                    };
+                                    self.graph.imports.push(ImportRow {
+                                        local: local.id.sym.to_string(),
+                                        imported,
+                                        source: src.clone(),
+                                        resolved: resolved.clone(),
+                                        span: self.span_file_lines(local.id.span),
+                                    });
+                                }
+                            }
+                            ObjectPatProp::Assign(a) => {
+                                self.graph.imports.push(ImportRow {
+                                    local: a.key.id.sym.to_string(),
+                                    imported: a.key.id.sym.to_string(),
+                                    source: src.clone(),
+                                    resolved: resolved.clone(),
+                                    span: self.span_file_lines(a.key.id.span),
+                                });
+                            }
+                            ObjectPatProp::Rest(_) => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+            d.visit_mut_children_with(self);
+            return;
+        }
         if let Some(name) = d.name.as_ident() {
             let def_span = if let Some(init) = &d.init {
                 self.span_file_lines(init.span())
@@ -1952,66 +3652,132 @@ impl VisitMut for CodePressTransform {
             };
             self.graph.defs.push(DefRow {
                 local: name.id.sym.to_string(),
+                id: id_pair(&name.id.to_id()),
                 kind: "var",
                 span: def_span,
+                scope: self.cur_scope(),
             });
 
-            // If this identifier is used as a JSX callsite and has an initializer, wrap it with __CP_stamp(init, id, fp)
-            if self.stamp_callsites && d.init.is_some() {
-                let sym = name.id.sym.to_string();
-                if self.callsite_symbols.contains(&sym) {
-                    let file = self.current_file();
-                    let enc = xor_encode(&file);
-                    // Move original initializer into call arg
-                    let orig = d.init.take().unwrap();
-                    d.init = Some(Box::new(Expr::Call(CallExpr {
-                        span: DUMMY_SP,
-                        callee: Callee::Expr(Box::new(Expr::Ident(cp_ident("__CP_stamp".into())))),
-                        args: vec![
-                            ExprOrSpread { spread: None, expr: orig },
-                            ExprOrSpread { spread: None, expr: Box::new(Expr::Lit(Lit::Str(Str { span: DUMMY_SP, value: format!("{}#{}", enc, sym).into(), raw: None }))) },
-                            ExprOrSpread { spread: None, expr: Box::new(Expr::Lit(Lit::Str(Str { span: DUMMY_SP, value: enc.into(), raw: None }))) },
-                        ],
-                        type_args: None,
-                        #[cfg(not(feature = "compat_0_87"))]
-                        ctxt: SyntaxContext::empty(),
-                    })));
-                }
+            // `callsite_symbols` only ever holds imports/hoisted-fn names (see the stampability
+            // gate in `visit_mut_jsx_element`) — a `const`/`let` declarator's own name can never
+            // land in it, so there's no in-place `__CP_stamp(...)` wrap to apply here. Local
+            // const/let/arrow components are simply not runtime-stamped: flushing a stamp for
+            // them at module top would reintroduce the TDZ crash that gate exists to avoid.
+        } else {
+            // Destructuring declarator, e.g. `const { a, b: c } = obj` / `const [x, ...rest] = arr` —
+            // record a `DefRow` for every bound name so downstream consumers see the same def
+            // coverage they'd get from a sequence of single-ident declarators.
+            let def_span = if let Some(init) = &d.init {
+                self.span_file_lines(init.span())
+            } else {
+                self.span_file_lines(d.name.span())
+            };
+            let mut idents = Vec::new();
+            collect_pat_idents(&d.name, &mut idents);
+            for ident in &idents {
+                self.graph.defs.push(DefRow {
+                    local: ident.sym.to_string(),
+                    id: id_pair(&ident.to_id()),
+                    kind: "var",
+                    span: def_span.clone(),
+                    scope: self.cur_scope(),
+                });
             }
         }
         d.visit_mut_children_with(self);
     }
 
     fn visit_mut_fn_decl(&mut self, n: &mut FnDecl) {
-        self.graph.defs.push(DefRow {
-            local: n.ident.sym.to_string(),
-            kind: "func",
-            span: self.span_file_lines(n.ident.span),
-        });
+        // Skip the `function __CP_stamp(...)` helper `ensure_stamp_helper_inline` injects before
+        // this traversal runs — it's our own synthetic wiring, not something the source module
+        // defined, so it shouldn't show up as a `DefRow` in the emitted graph.
+        if n.ident.sym.as_ref() != "__CP_stamp" {
+            self.graph.defs.push(DefRow {
+                local: n.ident.sym.to_string(),
+                id: id_pair(&n.ident.to_id()),
+                kind: "func",
+                span: self.span_file_lines(n.ident.span),
+                scope: self.cur_scope(),
+            });
+        }
+        self.enter_scope("function", n.function.span);
         n.visit_mut_children_with(self);
+        self.exit_scope();
     }
 
     fn visit_mut_class_decl(&mut self, n: &mut ClassDecl) {
         self.graph.defs.push(DefRow {
             local: n.ident.sym.to_string(),
+            id: id_pair(&n.ident.to_id()),
             kind: "class",
             span: self.span_file_lines(n.ident.span),
+            scope: self.cur_scope(),
         });
         n.visit_mut_children_with(self);
     }
 
+    fn visit_mut_arrow_expr(&mut self, n: &mut ArrowExpr) {
+        self.enter_scope("arrow", n.span);
+        n.visit_mut_children_with(self);
+        self.exit_scope();
+    }
+
+    fn visit_mut_block_stmt(&mut self, n: &mut BlockStmt) {
+        self.enter_scope("block", n.span);
+        n.visit_mut_children_with(self);
+        self.exit_scope();
+    }
+
     fn visit_mut_assign_expr(&mut self, n: &mut AssignExpr) {
         #[cfg(not(feature = "compat_0_87"))]
         {
             use swc_core::ecma::ast::{AssignTarget, SimpleAssignTarget};
             match &n.left {
                 AssignTarget::Simple(SimpleAssignTarget::Ident(b)) => {
-                    self.push_mutation_row(b.id.sym.to_string(), "".to_string(), "assign", n.span);
+                    self.push_mutation_row(b.id.sym.to_string(), Some(b.id.to_id()), "".to_string(), "assign", n.span);
                 }
                 AssignTarget::Simple(SimpleAssignTarget::Member(m)) => {
                     let mexpr = Expr::Member(m.clone());
-                    if let Some((root, path)) = self.static_member_path(&mexpr) {
-                        self.push_mutation_row(root, path, "assign", n.span);
+                    if let Some(exported) = match_commonjs_export(&mexpr) {
+                        let (local, local_id) = match &*n.right {
+                            Expr::Ident(id) => (id.sym.to_string(), Some(id_pair(&id.to_id()))),
+                            _ => ("<expr>".to_string(), None),
+                        };
+                        self.graph.exports.push(ExportRow {
+                            exported,
+                            local,
+                            local_id,
+                            span: self.span_file_lines(n.span),
+                        });
+                    } else if let Some((root, path)) = self.static_member_path(&mexpr) {
+                        self.push_mutation_row(root, None, path, "assign", n.span);
+                    }
+                }
+                AssignTarget::Pat(pat_target) => {
+                    use swc_core::ecma::ast::AssignTargetPat;
+                    let pat = match pat_target {
+                        AssignTargetPat::Array(a) => Some(Pat::Array(a.clone())),
+                        AssignTargetPat::Object(o) => Some(Pat::Object(o.clone())),
+                        AssignTargetPat::Invalid(_) => None,
+                    };
+                    if let Some(pat) = pat {
+                        // Destructuring assignment target, e.g. `({ a, b } = obj)` — record a
+                        // mutation per bound name, folding the RHS's static member path (when
+                        // available) into `path` so provenance still links back to the source object.
+                        let rhs_path = self
+                            .static_member_path(&n.right)
+                            .map(|(root, path)| format!("{}{}", root, path));
+                        let mut idents = Vec::new();
+                        collect_pat_idents(&pat, &mut idents);
+                        for ident in &idents {
+                            self.push_mutation_row(
+                                ident.sym.to_string(),
+                                Some(ident.to_id()),
+                                rhs_path.clone().unwrap_or_default(),
+                                "assign",
+                                n.span,
+                            );
+                        }
                     }
                 }
                 _ => {}
@@ -2024,18 +3790,45 @@ impl VisitMut for CodePressTransform {
             match &n.left {
                 PatOrExpr::Expr(e) => match &**e {
                     Expr::Ident(b) => {
-                        self.push_mutation_row(b.sym.to_string(), "".to_string(), "assign", n.span);
+                        self.push_mutation_row(b.sym.to_string(), Some(b.to_id()), "".to_string(), "assign", n.span);
                     }
                     Expr::Member(m) => {
                         let mexpr = Expr::Member(m.clone());
-                        if let Some((root, path)) = self.static_member_path(&mexpr) {
-                            self.push_mutation_row(root, path, "assign", n.span);
+                        if let Some(exported) = match_commonjs_export(&mexpr) {
+                            let (local, local_id) = match &*n.right {
+                                Expr::Ident(id) => (id.sym.to_string(), Some(id_pair(&id.to_id()))),
+                                _ => ("<expr>".to_string(), None),
+                            };
+                            self.graph.exports.push(ExportRow {
+                                exported,
+                                local,
+                                local_id,
+                                span: self.span_file_lines(n.span),
+                            });
+                        } else if let Some((root, path)) = self.static_member_path(&mexpr) {
+                            self.push_mutation_row(root, None, path, "assign", n.span);
                         }
                     }
                     _ => {}
                 },
-                PatOrExpr::Pat(_) => {
-                    // pattern assignment (e.g., destructuring) — skip for now
+                PatOrExpr::Pat(p) => {
+                    // Destructuring assignment target, e.g. `({ a, b } = obj)` — record a
+                    // mutation per bound name, folding the RHS's static member path (when
+                    // available) into `path` so provenance still links back to the source object.
+                    let rhs_path = self
+                        .static_member_path(&n.right)
+                        .map(|(root, path)| format!("{}{}", root, path));
+                    let mut idents = Vec::new();
+                    collect_pat_idents(&**p, &mut idents);
+                    for ident in &idents {
+                        self.push_mutation_row(
+                            ident.sym.to_string(),
+                            Some(ident.to_id()),
+                            rhs_path.clone().unwrap_or_default(),
+                            "assign",
+                            n.span,
+                        );
+                    }
                 }
             }
         }
@@ -2043,15 +3836,32 @@ impl VisitMut for CodePressTransform {
         n.visit_mut_children_with(self);
     }
 
+    fn visit_mut_await_expr(&mut self, n: &mut AwaitExpr) {
+        // `await import('./x')` — catch this before the generic `Callee::Import` check in
+        // `visit_mut_call_expr` sees the same call, so it's recorded as awaited.
+        if let Expr::Call(call) = &*n.arg {
+            if let Some(source) = match_dynamic_import_source(call) {
+                if self.dyn_import_seen.insert((call.span.lo().0, call.span.hi().0)) {
+                    self.graph.dyn_imports.push(DynImportRow {
+                        source,
+                        span: self.span_file_lines(call.span),
+                        awaited: true,
+                    });
+                }
+            }
+        }
+        n.visit_mut_children_with(self);
+    }
+
     fn visit_mut_update_expr(&mut self, n: &mut UpdateExpr) {
         match &*n.arg {
             Expr::Ident(i) => {
-                self.push_mutation_row(i.sym.to_string(), "".to_string(), "update", n.span)
+                self.push_mutation_row(i.sym.to_string(), Some(i.to_id()), "".to_string(), "update", n.span)
             }
             Expr::Member(m) => {
                 let mexpr = Expr::Member(m.clone());
                 if let Some((root, path)) = self.static_member_path(&mexpr) {
-                    self.push_mutation_row(root, path, "update", n.span);
+                    self.push_mutation_row(root, None, path, "update", n.span);
                 }
             }
             _ => {}
@@ -2060,6 +3870,39 @@ impl VisitMut for CodePressTransform {
     }
 
     fn visit_mut_call_expr(&mut self, n: &mut CallExpr) {
+        // Dynamic `import('./x')` — a first-class module edge even though it's an expression
+        // rather than a declaration. `await import(...)` is caught earlier by
+        // `visit_mut_await_expr`; here we additionally catch `import(...).then(...)` (by looking
+        // at the inner call through this call's member-expr callee) and a bare `import(...)`
+        // expression, deduping via `dyn_import_seen` against whichever path saw it first.
+        if let Some(source) = match_dynamic_import_source(n) {
+            if self.dyn_import_seen.insert((n.span.lo().0, n.span.hi().0)) {
+                self.graph.dyn_imports.push(DynImportRow {
+                    source,
+                    span: self.span_file_lines(n.span),
+                    awaited: false,
+                });
+            }
+        } else if let Callee::Expr(callee) = &n.callee {
+            if let Expr::Member(m) = &**callee {
+                if let MemberProp::Ident(prop) = &m.prop {
+                    if prop.sym.as_ref() == "then" {
+                        if let Expr::Call(inner) = &*m.obj {
+                            if let Some(source) = match_dynamic_import_source(inner) {
+                                if self.dyn_import_seen.insert((inner.span.lo().0, inner.span.hi().0)) {
+                                    self.graph.dyn_imports.push(DynImportRow {
+                                        source,
+                                        span: self.span_file_lines(inner.span),
+                                        awaited: true,
+                                    });
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
         // Object.assign(target, ...)
         if let Callee::Expr(callee) = &n.callee {
             if let Expr::Member(m) = &**callee {
@@ -2067,7 +3910,7 @@ impl VisitMut for CodePressTransform {
                     if obj.sym.as_ref() == "Object" && prop.sym.as_ref() == "assign" {
                         if let Some(first) = n.args.get(0) {
                             if let Some((root, path)) = self.static_member_path(&first.expr) {
-                                self.push_mutation_row(root, path, "call:Object.assign", n.span);
+                                self.push_mutation_row(root, None, path, "call:Object.assign", n.span);
                             }
                         }
                     } else {
@@ -2079,7 +3922,7 @@ impl VisitMut for CodePressTransform {
                                 "set" | "setIn" => "call:set",
                                 _ => "call:member",
                             };
-                            self.push_mutation_row(root, path, kind, n.span);
+                            self.push_mutation_row(root, None, path, kind, n.span);
                         }
                     }
                 }
@@ -2136,7 +3979,18 @@ impl VisitMut for CodePressTransform {
                     .next()
                     .map(|c| c.is_lowercase())
                     .unwrap_or(false);
-                if !is_host {
+                // Stamping runs as a module-top statement, before any `const`/`let`/`class`
+                // local's own initializer has run — so stamping one of those would throw a
+                // temporal-dead-zone `ReferenceError` at load and crash the whole module. Only
+                // imports and hoisted `function` declarations are actually safe to reference that
+                // early; skip everything else (including unresolved/global identifiers, since
+                // there's no binding to vouch for them either).
+                let is_stampable = self
+                    .bindings
+                    .get(&id.to_id())
+                    .map(|b| b.import.is_some() || b.fn_body_span.is_some())
+                    .unwrap_or(false);
+                if !is_host && is_stampable {
                     let sym = id.sym.to_string();
                     if !self.callsite_symbols.contains(&sym) {
                         self.callsite_symbols.insert(sym.clone());
@@ -2158,22 +4012,9 @@ impl VisitMut for CodePressTransform {
                                     ctxt: SyntaxContext::empty(),
                                 }))
                             }));
-                            // Prepend so it’s available early; order after helpers is fine
-                            // Insert after any previously inserted helpers (provider + stamp)
-                            // For simplicity, push at start+1
-                            // Ensure we at least have one body slot
-                            // Using file_from_span already set module_file
-                            // Here we conservatively insert near top
-                            // Note: it may duplicate across files if sym collides; guarded at runtime
-                            // Insert after index 1 when helpers exist
-                            // We'll just insert at 0; helpers were inserted earlier so this shifts them, still fine
-                            // (no semantic change)
-                            // To keep helper first, insert at 1 if body has >=1
-                            let insert_at = if let Some(first) = self.module_file.as_ref() { 1 } else { 0 };
-                            // Can't mutate m.body here; collect for later is heavy. Instead, append to graph via inject_graph_stmt? Simpler: store it into graph literal? For now, push to a temp queue is complex.
-                            // Fallback: attach to opening.attrs for provenance only; stamping still done by export/module path. Skip injecting extra statement to avoid structural changes late.
-                            // Leaving runtime callsite injection out to avoid ordering hazards inside this function.
-                            let _ = insert_at; // placeholder to keep compile warnings away
+                            // Can't mutate `m.body` from inside a JSX-element visitor — accumulate
+                            // and let `visit_mut_module` flush these after the module finishes.
+                            self.pending_callsite_stmts.push(call);
                         }
                     }
                 }
@@ -2261,7 +4102,7 @@ impl VisitMut for CodePressTransform {
 
         // Build payloads
         let mut candidates = self.rank_candidates(&all_nodes);
-        let kinds = Self::aggregate_kinds(&all_nodes);
+        let kinds = self.aggregate_kinds(&all_nodes);
 
         if !orig_full_span.is_dummy() {
             if let Some(line_info) = self.get_line_info(orig_open_span, Some(orig_full_span)) {
@@ -2289,6 +4130,30 @@ impl VisitMut for CodePressTransform {
         let symrefs_json = serde_json::to_string(&symrefs).unwrap_or_else(|_| "[]".into());
         let symrefs_enc = xor_encode(&symrefs_json);
 
+        // Structured sidecar entry for this callsite — same id as the `codepress-data-fp` attr,
+        // but as numeric fields plus the resolved `SymbolRef`s, so an overlay can jump to a
+        // definition without re-deriving positions from the encoded attribute string.
+        if let JSXAttrOrSpread::JSXAttr(fp_attr) =
+            self.create_encoded_path_attr(&filename, orig_open_span, Some(orig_full_span))
+        {
+            if let Some(JSXAttrValue::Lit(Lit::Str(s))) = fp_attr.value {
+                let callsite_id = s.value.to_string();
+                if let Some((start_line, start_col, end_line, end_col)) =
+                    self.line_col_range(orig_open_span, Some(orig_full_span))
+                {
+                    self.graph.span_map.push(SpanMapRow {
+                        callsite_id,
+                        file: normalize_filename(&filename),
+                        start_line,
+                        start_col,
+                        end_line,
+                        end_col,
+                        symbol_refs: symrefs.clone(),
+                    });
+                }
+            }
+        }
+
         // Always-on behavior for custom component callsites (excluding skip list):
         let is_custom_call = !is_host
             && Self::is_custom_component_name(&node.opening.name)
@@ -2358,27 +4223,32 @@ impl VisitMut for CodePressTransform {
             self.wrap_with_provider(node, meta);
             */
 
+            let opening_span = node.opening.span;
             let attrs = &mut node.opening.attrs;
             // Only annotate the injected wrappers (provider or host wrapper), not the invocation element
-            CodePressTransform::attach_attr_string(attrs, "data-codepress-edit-candidates", cands_enc.clone());
-            CodePressTransform::attach_attr_string(attrs, "data-codepress-source-kinds", kinds_enc.clone());
-            CodePressTransform::attach_attr_string(attrs, "data-codepress-symbol-refs", symrefs_enc.clone());
+            CodePressTransform::attach_attr_string(attrs, "data-codepress-edit-candidates", cands_enc.clone(), opening_span);
+            CodePressTransform::attach_attr_string(attrs, "data-codepress-source-kinds", kinds_enc.clone(), opening_span);
+            CodePressTransform::attach_attr_string(attrs, "data-codepress-symbol-refs", symrefs_enc.clone(), opening_span);
         } else {
             // Host element → tag directly
+            let opening_span = node.opening.span;
             CodePressTransform::attach_attr_string(
                 &mut node.opening.attrs,
                 "data-codepress-edit-candidates",
                 cands_enc.clone(),
+                opening_span,
             );
             CodePressTransform::attach_attr_string(
                 &mut node.opening.attrs,
                 "data-codepress-source-kinds",
                 kinds_enc.clone(),
+                opening_span,
             );
             CodePressTransform::attach_attr_string(
                 &mut node.opening.attrs,
                 "data-codepress-symbol-refs",
                 symrefs_enc.clone(),
+                opening_span,
             );
             if !Self::has_attr_key(&node.opening.attrs, "data-codepress-callsite") {
                 if let JSXAttrOrSpread::JSXAttr(a) = self.create_encoded_path_attr(
@@ -2387,7 +4257,7 @@ impl VisitMut for CodePressTransform {
                     Some(node.span),
                 ) {
                     node.opening.attrs.push(JSXAttrOrSpread::JSXAttr(JSXAttr {
-                        span: DUMMY_SP,
+                        span: opening_span,
                         name: JSXAttrName::Ident(cp_ident_name("data-codepress-callsite".into())),
                         value: a.value,
                     }));
@@ -2401,33 +4271,41 @@ impl VisitMut for CodePressTransform {
 // Pass 2: hoist wrapper attrs to child & remove wrapper
 // -----------------------------------------------------------------------------
 
+// `wrapper_tag`/the hoist keys are compared against on every attr of every JSX element in the
+// tree, so they're interned once as `Atom`s up front (same representation `Ident::sym` already
+// uses) rather than re-comparing against a `&str` byte-for-byte on each check — mirrors the
+// `SymbolStr` removal that made rustc stop allocating transient strings just to compare interned
+// symbols.
 struct HoistAndElide {
-    wrapper_tag: String,
-    keys: Vec<String>,
+    wrapper_tag: swc_core::ecma::atoms::Atom,
+    keys: Vec<swc_core::ecma::atoms::Atom>,
 }
 
 impl HoistAndElide {
     fn is_wrapper(&self, name: &JSXElementName) -> bool {
         match name {
-            JSXElementName::Ident(id) => id.sym.as_ref() == self.wrapper_tag,
+            JSXElementName::Ident(id) => id.sym == self.wrapper_tag,
             _ => false,
         }
     }
-    fn has_attr(attrs: &[JSXAttrOrSpread], key: &str) -> bool {
+    fn has_attr(attrs: &[JSXAttrOrSpread], key: &swc_core::ecma::atoms::Atom) -> bool {
         attrs.iter().any(|a| {
             if let JSXAttrOrSpread::JSXAttr(attr) = a {
                 if let JSXAttrName::Ident(id) = &attr.name {
-                    return id.sym.as_ref() == key;
+                    return id.sym == *key;
                 }
             }
             false
         })
     }
-    fn get_attr_string(attrs: &[JSXAttrOrSpread], key: &str) -> Option<String> {
+    fn get_attr_string(
+        attrs: &[JSXAttrOrSpread],
+        key: &swc_core::ecma::atoms::Atom,
+    ) -> Option<String> {
         for a in attrs {
             if let JSXAttrOrSpread::JSXAttr(attr) = a {
                 if let JSXAttrName::Ident(id) = &attr.name {
-                    if id.sym.as_ref() == key {
+                    if id.sym == *key {
                         if let Some(JSXAttrValue::Lit(Lit::Str(s))) = &attr.value {
                             return Some(s.value.to_string());
                         }
@@ -2437,12 +4315,17 @@ impl HoistAndElide {
         }
         None
     }
-    fn push_attr(attrs: &mut Vec<JSXAttrOrSpread>, key: &str, val: String) {
+    fn push_attr(
+        attrs: &mut Vec<JSXAttrOrSpread>,
+        key: &swc_core::ecma::atoms::Atom,
+        val: String,
+        span: swc_core::common::Span,
+    ) {
         attrs.push(JSXAttrOrSpread::JSXAttr(JSXAttr {
-            span: DUMMY_SP,
-            name: JSXAttrName::Ident(cp_ident_name(key.into())),
+            span,
+            name: JSXAttrName::Ident(cp_ident_name(key)),
             value: Some(JSXAttrValue::Lit(Lit::Str(Str {
-                span: DUMMY_SP,
+                span,
                 value: val.into(),
                 raw: None,
             }))),
@@ -2470,10 +4353,11 @@ impl VisitMut for HoistAndElide {
         let mut child = child_el;
 
         // Hoist keys if missing on child
+        let child_span = child.opening.span;
         for key in &self.keys {
             if !Self::has_attr(&child.opening.attrs, key) {
                 if let Some(val) = Self::get_attr_string(&node.opening.attrs, key) {
-                    Self::push_attr(&mut child.opening.attrs, key, val);
+                    Self::push_attr(&mut child.opening.attrs, key, val, child_span);
                 }
             }
         }
@@ -2483,6 +4367,206 @@ impl VisitMut for HoistAndElide {
     }
 }
 
+// -----------------------------------------------------------------------------
+// Strip pass: reverse of stamping, for clean production output (`mode: "strip"`)
+// -----------------------------------------------------------------------------
+
+/// Removes every injected `codepress-*`/`data-codepress-*` attribute and unwraps the synthetic
+/// `<codepress-marker>`/`__CPProvider`/`__CPX` elements, so a build that ran the stamping pass in
+/// dev can ship clean markup in production. Safe to run on an un-instrumented file: with nothing
+/// matching `strip_attr_prefixes` or `is_synthetic`, it's a no-op.
+struct StripInstrumentation {
+    wrapper_tag: String,
+    provider_ident: String,
+    strip_attr_prefixes: Vec<String>,
+}
+
+impl StripInstrumentation {
+    fn is_synthetic(&self, name: &JSXElementName) -> bool {
+        match name {
+            JSXElementName::Ident(id) => {
+                let n = id.sym.as_ref();
+                n == self.wrapper_tag || n == self.provider_ident || n == "__CPX"
+            }
+            JSXElementName::JSXMemberExpr(m) => {
+                let mut obj = &m.obj;
+                while let JSXObject::JSXMemberExpr(inner) = obj {
+                    obj = &inner.obj;
+                }
+                if let JSXObject::Ident(root) = obj {
+                    let n = root.sym.as_ref();
+                    n == "__CPX" || n == self.provider_ident
+                } else {
+                    false
+                }
+            }
+            JSXElementName::JSXNamespacedName(_) => false,
+        }
+    }
+
+    fn should_strip_attr(&self, key: &str) -> bool {
+        self.strip_attr_prefixes
+            .iter()
+            .any(|prefix| key.starts_with(prefix.as_str()))
+    }
+}
+
+impl VisitMut for StripInstrumentation {
+    fn visit_mut_jsx_element(&mut self, node: &mut JSXElement) {
+        node.visit_mut_children_with(self);
+
+        node.opening.attrs.retain(|a| match a {
+            JSXAttrOrSpread::JSXAttr(attr) => match &attr.name {
+                JSXAttrName::Ident(id) => !self.should_strip_attr(id.sym.as_ref()),
+                _ => true,
+            },
+            JSXAttrOrSpread::SpreadElement(_) => true,
+        });
+
+        // Unwrap a synthetic wrapper with exactly one JSXElement child (mirrors HoistAndElide's
+        // replace-in-place trick, which works whether `node` lives in a children list or is the
+        // sole argument of a `return`).
+        if self.is_synthetic(&node.opening.name) && node.children.len() == 1 {
+            let child_el = match node.children.remove(0) {
+                JSXElementChild::JSXElement(boxed) => *boxed,
+                other => {
+                    node.children.push(other);
+                    return;
+                }
+            };
+            *node = child_el;
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Pass 4: tree-shake the wrapper/provider import if elision left it dangling
+// -----------------------------------------------------------------------------
+
+// Counts every `Ident` reference in the module, so a name that turns out to be mentioned only
+// in its own `import` statement (which we skip walking below) counts as unused — the same rule
+// next-swc's `shake_exports`/`next_ssg` dead-import elimination applies.
+struct UsageCounter {
+    used: HashSet<String>,
+}
+
+impl Visit for UsageCounter {
+    fn visit_ident(&mut self, n: &Ident) {
+        self.used.insert(n.sym.to_string());
+    }
+}
+
+fn import_local_name(spec: &ImportSpecifier) -> &Ident {
+    match spec {
+        ImportSpecifier::Named(s) => &s.local,
+        ImportSpecifier::Default(s) => &s.local,
+        ImportSpecifier::Namespace(s) => &s.local,
+    }
+}
+
+// The `Ident` an `AssignExpr`'s member-expression target is hung off (e.g. the `__CPX` in
+// `__CPX.displayName = ...`), across both `AssignExpr::left` representations this crate supports.
+fn assign_target_obj_ident(n: &AssignExpr) -> Option<&Ident> {
+    #[cfg(not(feature = "compat_0_87"))]
+    {
+        use swc_core::ecma::ast::{AssignTarget, SimpleAssignTarget};
+        if let AssignTarget::Simple(SimpleAssignTarget::Member(m)) = &n.left {
+            if let Expr::Ident(id) = &*m.obj {
+                return Some(id);
+            }
+        }
+    }
+    #[cfg(feature = "compat_0_87")]
+    {
+        use swc_core::ecma::ast::PatOrExpr;
+        if let PatOrExpr::Expr(e) = &n.left {
+            if let Expr::Member(m) = &**e {
+                if let Expr::Ident(id) = &*m.obj {
+                    return Some(id);
+                }
+            }
+        }
+    }
+    None
+}
+
+// True for a `ModuleItem` that's part of the provider preamble `ensure_provider_inline` injects —
+// the `const __CPX = createContext(null)` declarator, its `__CPX.displayName = "CPX"` assignment,
+// and the `__CPProvider` function itself. These all exist solely to consume the `createContext`
+// import, so a usage scan has to skip them or it'll always find `createContext`/`__CPX` "used" by
+// their own defining statements, masking the import as live even when nothing else touches it.
+// Matched structurally (not by position) so it stays correct regardless of what else
+// `process_transform` inserts ahead of this block (e.g. the runtime graph-injection statement).
+fn is_provider_preamble_item(item: &ModuleItem, provider_ident: &str) -> bool {
+    match item {
+        ModuleItem::Stmt(Stmt::Decl(Decl::Var(var))) => var.decls.iter().any(|d| {
+            matches!(&d.name, Pat::Ident(id) if id.id.sym.as_ref() == "__CPX")
+        }),
+        ModuleItem::Stmt(Stmt::Decl(Decl::Fn(f))) => f.ident.sym.as_ref() == provider_ident,
+        ModuleItem::Stmt(Stmt::Expr(ExprStmt { expr, .. })) => match &**expr {
+            Expr::Assign(a) => assign_target_obj_ident(a)
+                .map(|id| id.sym.as_ref() == "__CPX")
+                .unwrap_or(false),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+struct DeadImportEliminator {
+    wrapper_tag: String,
+    provider_ident: String,
+}
+
+impl VisitMut for DeadImportEliminator {
+    fn visit_mut_module(&mut self, m: &mut Module) {
+        let mut counter = UsageCounter {
+            used: HashSet::new(),
+        };
+        for item in m.body.iter() {
+            if matches!(item, ModuleItem::ModuleDecl(ModuleDecl::Import(_))) {
+                continue;
+            }
+            if is_provider_preamble_item(item, &self.provider_ident) {
+                continue;
+            }
+            item.visit_with(&mut counter);
+        }
+
+        let mut provider_import_dead = false;
+        m.body.retain_mut(|item| {
+            let import = match item {
+                ModuleItem::ModuleDecl(ModuleDecl::Import(i)) => i,
+                _ => return true,
+            };
+            import.specifiers.retain(|spec| {
+                let sym = import_local_name(spec).sym.as_ref();
+                if sym != self.wrapper_tag
+                    && sym != self.provider_ident
+                    && sym != PROVIDER_IMPORT_LOCAL
+                {
+                    return true;
+                }
+                let alive = counter.used.contains(sym);
+                if sym == PROVIDER_IMPORT_LOCAL && !alive {
+                    provider_import_dead = true;
+                }
+                alive
+            });
+            !import.specifiers.is_empty()
+        });
+
+        // The `createContext` import is gone — the `__CPX`/`__CPProvider` preamble that only
+        // existed to consume it is now dead too, so drop the whole unit instead of leaving
+        // `const __CPX = createContext(null)` behind to throw a `ReferenceError` at load.
+        if provider_import_dead {
+            let provider_ident = self.provider_ident.clone();
+            m.body
+                .retain(|item| !is_provider_preamble_item(item, &provider_ident));
+        }
+    }
+}
+
 // -----------------------------------------------------------------------------
 // Entrypoint
 // -----------------------------------------------------------------------------
@@ -2492,35 +4576,97 @@ pub fn process_transform(
     mut program: Program,
     metadata: TransformPluginProgramMetadata,
 ) -> Program {
-    let config = metadata
+    let config: HashMap<String, serde_json::Value> = metadata
         .get_transform_plugin_config()
         .map(|s| serde_json::from_str(&s).unwrap_or_default())
         .unwrap_or_default();
 
+    // `mode: "strip"` (legacy) or `strip: true` both mean "production build": skip Pass 1
+    // (the expensive per-element instrumentation + binding collection) entirely, then run a
+    // cleanup pass matching `strip_attr_pattern` (default `^data-codepress-`, merged with any
+    // `stripAttrPrefixes` override) after Pass 2 so the same toolchain degrades to a no-op plus
+    // cleanup instead of needing a separate production build path.
+    let strip_mode = config.get("mode").and_then(|v| v.as_str()) == Some("strip")
+        || config.get("strip").and_then(|v| v.as_bool()) == Some(true);
+    let mut strip_attr_prefixes: Vec<String> = config
+        .get("stripAttrPrefixes")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_else(|| vec!["codepress-".to_string()]);
+    let strip_attr_pattern = config
+        .get("stripAttrPattern")
+        .and_then(|v| v.as_str())
+        .unwrap_or("^data-codepress-")
+        .to_string();
+    // `should_strip_attr` only ever did a prefix check, so a caret-anchored pattern like
+    // `^data-codepress-` is handled by dropping the anchor and matching the rest as a prefix —
+    // this plugin stays dependency-light rather than pulling in a full regex engine for what is,
+    // in practice, always a prefix check (mirrors `react_remove_properties`'s default pattern).
+    strip_attr_prefixes.push(strip_attr_pattern.trim_start_matches('^').to_string());
+
     // Convert PluginSourceMapProxy to Arc<dyn SourceMapper>
     let source_map: Option<std::sync::Arc<dyn SourceMapper>> =
         Some(std::sync::Arc::new(metadata.source_map));
+    let comments: Option<std::sync::Arc<dyn swc_core::common::comments::Comments>> =
+        metadata.comments.clone().map(|c| std::sync::Arc::new(c) as _);
+
+    if !strip_mode {
+        // Assign each `Ident` a unique `SyntaxContext` so shadowed bindings (e.g. a top-level
+        // `const user` and an inner `const user`) are distinguishable via `to_id()` everywhere
+        // below, instead of every identifier sharing the same empty context.
+        let unresolved_mark = Mark::new();
+        let top_level_mark = Mark::new();
+        program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, true));
+    }
 
-    let mut transform = CodePressTransform::new(config, source_map);
+    let mut transform = CodePressTransform::new(config, source_map, comments);
 
-    // Collect bindings once up-front (to resolve inits/imports/functions)
-    transform.collect_bindings(&program);
+    if !strip_mode {
+        // Collect bindings once up-front (to resolve inits/imports/functions)
+        transform.collect_bindings(&program);
 
-    // Pass 1: main transform
-    program.visit_mut_with(&mut transform);
+        // Pass 1: main transform
+        program.visit_mut_with(&mut transform);
+    }
 
-    // Pass 2: always hoist & elide (remove wrappers, keep data on child callsite)
+    // Pass 2: always hoist & elide (remove wrappers, keep data on child callsite) — a no-op when
+    // Pass 1 was skipped above, since there's nothing to hoist.
     let mut elider = HoistAndElide {
-        wrapper_tag: transform.wrapper_tag.clone(),
+        wrapper_tag: transform.wrapper_tag.clone().into(),
         keys: vec![
-            "data-codepress-edit-candidates".to_string(),
-            "data-codepress-source-kinds".to_string(),
-            "data-codepress-callsite".to_string(),
-            "data-codepress-symbol-refs".to_string(),
+            "data-codepress-edit-candidates".into(),
+            "data-codepress-source-kinds".into(),
+            "data-codepress-callsite".into(),
+            "data-codepress-symbol-refs".into(),
         ],
     };
     program.visit_mut_with(&mut elider);
 
+    if strip_mode {
+        // Pass 3 (strip mode only): remove any `data-codepress-*`/`codepress-*` attributes and
+        // unwrap any synthetic elements that made it this far — a no-op on freshly-authored
+        // source, but also safe to run over already-instrumented markup (e.g. re-processed dev
+        // output) since nothing here assumes Pass 1 ran.
+        let mut stripper = StripInstrumentation {
+            wrapper_tag: transform.wrapper_tag.clone(),
+            provider_ident: transform.provider_ident.clone(),
+            strip_attr_prefixes,
+        };
+        program.visit_mut_with(&mut stripper);
+    }
+
+    // Pass 4: drop the wrapper/provider `ImportSpecifier` if nothing above left a reference to
+    // it standing — elision (Pass 2) and stripping (Pass 3) both frequently remove every use.
+    let mut dead_import_eliminator = DeadImportEliminator {
+        wrapper_tag: transform.wrapper_tag.clone(),
+        provider_ident: transform.provider_ident.clone(),
+    };
+    program.visit_mut_with(&mut dead_import_eliminator);
+
     program
 }
 
@@ -2538,12 +4684,34 @@ struct ProviderMeta {
 // -----------------------------------------------------------------------------
 // Extra types/helpers for symbol-refs & literal index
 // -----------------------------------------------------------------------------
-#[derive(serde::Serialize)]
+/// Where a `SymbolRef`'s `local` name actually comes from — the per-file half of a
+/// rust-analyzer-style `source_to_def` mapping, letting a host aggregator follow a JSX callsite
+/// across the import boundary to the module that defines it instead of stopping at the local
+/// binding name.
+#[derive(Clone, serde::Serialize)]
+#[serde(tag = "kind")]
+enum SymbolOrigin {
+    Import {
+        module_specifier: String, // "./widgets", exactly as written (see `ImportInfo::source`)
+        resolved: String,         // canonicalized via `Resolver::resolve`
+        imported_name: String,    // the name in the source module ("default"/"*"/a named export)
+        is_default: bool,
+        is_namespace: bool,
+    },
+    Local {
+        def_span: String,
+    },
+}
+
+#[derive(Clone, serde::Serialize)]
 struct SymbolRef {
     file: String,
     local: String,
     path: String,
     span: String,
+    // `None` when `local` isn't a binding this module collected at all (e.g. a global like
+    // `window`), so there's nothing to resolve an origin from.
+    origin: Option<SymbolOrigin>,
 }
 
 impl CodePressTransform {
@@ -2588,8 +4756,70 @@ impl CodePressTransform {
                     path: prefix,
                     text: s.value.to_string(),
                     span: self.span_file_lines(s.span),
+                    kind: "string",
                 });
             }
+            Expr::Tpl(t) if t.exprs.is_empty() => {
+                // No interpolations — the raw source text of the single quasi (escape sequences
+                // left as written, not cooked) is the whole literal.
+                if let Some(q) = t.quasis.first() {
+                    let text = q.raw.to_string();
+                    self.graph.literal_index.push(LiteralIxRow {
+                        export_name: export_name.to_string(),
+                        path: prefix,
+                        text,
+                        span: self.span_file_lines(t.span),
+                        kind: "template",
+                    });
+                }
+            }
+            Expr::Tpl(t) => {
+                // Interpolated — index each static quasi segment on its own sub-path so an editor
+                // can round-trip an edit to just that segment without touching the `${...}` parts.
+                for (idx, q) in t.quasis.iter().enumerate() {
+                    let text = q.raw.to_string();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    self.graph.literal_index.push(LiteralIxRow {
+                        export_name: export_name.to_string(),
+                        path: format!("{prefix}#quasi[{idx}]"),
+                        text,
+                        span: self.span_file_lines(q.span),
+                        kind: "template",
+                    });
+                }
+            }
+            Expr::Cond(c) => {
+                self.harvest_literal_index(export_name, &c.cons, format!("{prefix}?consequent"));
+                self.harvest_literal_index(export_name, &c.alt, format!("{prefix}?alternate"));
+            }
+            Expr::JSXElement(el) => {
+                for (idx, child) in el.children.iter().enumerate() {
+                    let path = push_key(&prefix, &format!("children[{idx}]"));
+                    match child {
+                        JSXElementChild::JSXText(t) => {
+                            let text = t.value.to_string();
+                            if text.trim().is_empty() {
+                                continue;
+                            }
+                            self.graph.literal_index.push(LiteralIxRow {
+                                export_name: export_name.to_string(),
+                                path,
+                                text,
+                                span: self.span_file_lines(t.span),
+                                kind: "jsx-text",
+                            });
+                        }
+                        JSXElementChild::JSXExprContainer(container) => {
+                            if let JSXExpr::Expr(expr) = &container.expr {
+                                self.harvest_literal_index(export_name, expr, path);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
             _ => {}
         }
     }